@@ -0,0 +1,67 @@
+use cosmos_sdk_proto::cosmos::authz::v1beta1::MsgExec;
+use cosmos_sdk_proto::traits::Message;
+use cosmwasm_std::Binary;
+use pyxis_sm::msg::SdkMsg;
+
+use crate::error::ContractError;
+
+// messages executing other messages with the signer of the wrapper message
+// gov and group proposals execute their messages with another signer and are not unwrapped
+const AUTHZ_EXEC: &str = "/cosmos.authz.v1beta1.MsgExec";
+
+// maximum depth of nested wrapper messages
+const MAX_NESTED_DEPTH: u32 = 5;
+
+/// UnwrappedMsg is a message of a transaction or a message executed by a wrapper message
+pub struct UnwrappedMsg {
+    pub msg: SdkMsg,
+    // the message is executed by a wrapper message
+    pub nested: bool,
+}
+
+/// return the messages of a transaction and the messages executed by their wrapper messages,
+/// recursively, wrapper messages are kept before the messages they execute
+pub fn unwrap_msgs(msgs: &[SdkMsg]) -> Result<Vec<UnwrappedMsg>, ContractError> {
+    let mut unwrapped_msgs: Vec<UnwrappedMsg> = Vec::new();
+    unwrap_into(msgs, false, 0, &mut unwrapped_msgs)?;
+    Ok(unwrapped_msgs)
+}
+
+fn unwrap_into(
+    msgs: &[SdkMsg],
+    nested: bool,
+    depth: u32,
+    unwrapped_msgs: &mut Vec<UnwrappedMsg>,
+) -> Result<(), ContractError> {
+    if depth > MAX_NESTED_DEPTH {
+        return Err(ContractError::TooManyNestedMessages {
+            max_depth: MAX_NESTED_DEPTH,
+        });
+    }
+
+    for msg in msgs {
+        unwrapped_msgs.push(UnwrappedMsg {
+            msg: msg.clone(),
+            nested,
+        });
+
+        if msg.type_url == AUTHZ_EXEC {
+            let msg_exec = MsgExec::decode(msg.value.as_slice()).map_err(|_| {
+                ContractError::MalformedMessage {
+                    type_url: msg.type_url.clone(),
+                }
+            })?;
+            let inner_msgs: Vec<SdkMsg> = msg_exec
+                .msgs
+                .into_iter()
+                .map(|any| SdkMsg {
+                    type_url: any.type_url,
+                    value: Binary::from(any.value),
+                })
+                .collect();
+            unwrap_into(&inner_msgs, true, depth + 1, unwrapped_msgs)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,27 +1,54 @@
+use std::collections::BTreeMap;
 use std::vec;
 
 use cosmos_sdk_proto::traits::{Message, Name};
-use cosmwasm_std::{to_json_binary, CosmosMsg, Order, StdError};
+use cosmwasm_std::{to_json_binary, CosmosMsg, Order};
 
+use cosmos_sdk_proto::cosmos::bank::v1beta1::MsgSend;
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin as ProtoCoin;
 use cosmos_sdk_proto::cosmwasm::wasm::v1::MsgExecuteContract;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    wasm_execute, Addr, Binary, ContractInfoResponse, Deps, DepsMut, Env, MessageInfo,
-    QueryRequest, Reply, Response, StdResult, WasmQuery,
+    instantiate2_address, wasm_execute, Addr, BankMsg, Binary, CodeInfoResponse, Coin,
+    ContractInfoResponse, Deps, DepsMut, Env, Event, MessageInfo, QuerierWrapper, QueryRequest,
+    Reply, Response, StdError, StdResult, Storage, SubMsg, Uint64, WasmMsg, WasmQuery,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw_storage_plus::Bound;
+use serde::de::IgnoredAny;
 use serde_json_wasm::de::Error;
+use sha2::{Digest, Sha256};
 
+use crate::authz::{unwrap_msgs, UnwrappedMsg};
 use crate::error::ContractError;
-use crate::msg::{AllPluginsResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{Config, Plugin, PluginStatus, CONFIG, PLUGINS};
+use crate::migrations::{migrate_state, parse_version};
+use crate::msg::{
+    AllPluginsResponse, ConfigResponse, ContractVersionResponse, ExecuteMsg, InstantiateMsg,
+    MigrateMsg, PendingActionsResponse, PluginOp, PluginResponse, PluginVerdict,
+    PluginsByTypeResponse, QueryMsg, RecoveryLogResponse, RecoveryStatusResponse,
+    SessionKeysResponse, SimulateExecuteResponse,
+};
+use crate::session_keys::{check_session_key, validate_session_key};
+use crate::state::{
+    BalanceSnapshot, CallType, Config, MessageFilter, PendingAction, Plugin, PluginScope,
+    PluginStatus, RecoveryPolicy, RecoveryRecord, RecoveryTally, ScopedMsgType, SensitiveAction,
+    SessionKey, BALANCE_SNAPSHOT, CONFIG, NEXT_ACTION_ID, PENDING_ACTIONS, PLUGINS, RECOVERY_LOG,
+    RECOVERY_NONCE, RECOVERY_TALLY, SESSION_KEYS, TX_COUNTER, UNFREEZE_APPROVALS,
+};
 
 use pyxis_sm::msg::{
-    CallInfo, PyxisPluginExecuteMsg, PyxisRecoveryPluginExecuteMsg, PyxisSudoMsg, SdkMsg,
+    BalanceChange, CallInfo, InterfaceInfoResponse, PluginHook, PyxisPluginExecuteMsg,
+    PyxisPluginQueryMsg, PyxisRecoveryPluginExecuteMsg, PyxisSudoMsg, SdkMsg,
+    TrackedDenomsResponse, ValidateExecuteResponse, BALANCE_CHANGES_INTERFACE_VERSION,
+    LEGACY_PLUGIN_INTERFACE_VERSION, PLUGIN_INTERFACE_VERSION, RECOVERY_NONCE_INTERFACE_VERSION,
+};
+use pyxis_sm::plugin_manager_msg::{
+    AllPluginsResponse as PMAllPluginsResponse,
+    DisabledPluginsResponse as PMDisabledPluginsResponse, ExecuteMsg as PMExecuteMsg,
+    PluginResponse as PMPluginResponse, PluginTemplateResponse as PMPluginTemplateResponse,
+    PluginType, QueryMsg as PMQueryMsg,
 };
-use pyxis_sm::plugin_manager_msg::{PluginResponse, PluginType, QueryMsg as PMQueryMsg};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:pyxis-sm-base";
@@ -31,11 +58,32 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 
+// default maximum total gas of the plugins called in a hook
+const DEFAULT_PLUGIN_GAS_BUDGET: u64 = 2_000_000;
+
+// reply ids
+const RECOVER_REPLY_ID: u64 = 1;
+const NOTIFY_PLUGIN_MANAGER_REPLY_ID: u64 = 2;
+
+// oldest version of the plugin interface this smart account can encode hook messages in
+const MIN_PLUGIN_INTERFACE_VERSION: u32 = LEGACY_PLUGIN_INTERFACE_VERSION;
+
+// execute messages of the plugin interface, only sent to the plugins by this smart account
+const PLUGIN_EXECUTE_MSGS: [&str; 5] = [
+    "register",
+    "unregister",
+    "update_config",
+    "pre_execute",
+    "after_execute",
+];
+const RECOVERY_PLUGIN_EXECUTE_MSGS: [&str; 4] =
+    ["register", "unregister", "update_config", "recover"];
+
 /// Handling contract instantiation
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -45,23 +93,126 @@ pub fn instantiate(
         deps.storage,
         &Config {
             plugin_manager_addr: msg.plugin_manager_addr,
-            recoverable: false,
+            plugin_gas_budget: msg.plugin_gas_budget.unwrap_or(DEFAULT_PLUGIN_GAS_BUDGET),
+            recoverable: None,
+            session_keys_only: false,
+            guardians: vec![],
+            frozen: false,
+            timelock: 0,
         },
     )?;
 
-    Ok(Response::new()
+    let mut response = Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("owner", info.sender);
+
+    // initial plugins are checked against the plugin manager as in `register_plugin`,
+    // their `Register` calls are dispatched in this response
+    let mut plugin_infos = PluginInfos::new(deps.storage)?;
+    for plugin in msg.initial_plugins {
+        let op_response = execute_plugin_op(
+            deps.branch(),
+            &env,
+            &mut plugin_infos,
+            PluginOp::Register {
+                plugin_address: plugin.plugin_address,
+                config: plugin.config,
+                filter: None,
+                scope: None,
+            },
+            vec![],
+        )?;
+        response = response
+            .add_submessages(op_response.messages)
+            .add_attributes(op_response.attributes)
+            .add_events(op_response.events);
+    }
+
+    Ok(response)
 }
 
+/// Handling contract migration
+/// the state is transformed from the stored version to the current version
+/// migrating to an older version is not allowed
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
-    match msg {
-        // Find matched incoming message variant and execute them with your custom logic.
-        //
-        // With `Response` type, it is possible to dispatch message to invoke external logic.
-        // See: https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#dispatching-messages
+pub fn migrate(mut deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigrationContract {
+            contract: stored.contract,
+        });
+    }
+
+    let from_version = parse_version(&stored.version)?;
+    if from_version > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::MigrationDowngrade {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    migrate_state(deps.branch(), from_version, DEFAULT_PLUGIN_GAS_BUDGET)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let suspend_events = if msg.check_plugins {
+        check_plugins(deps)?
+    } else {
+        vec![]
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_events(suspend_events))
+}
+
+/// check every registered plugin against the plugin manager
+/// plugins which are disabled by the plugin manager or migrated are suspended,
+/// the max gas of the other plugins is updated to the one declared in the plugin manager
+/// and plugins the plugin manager can not answer for are left unchanged
+fn check_plugins(deps: DepsMut) -> StdResult<Vec<Event>> {
+    let plugin_manager_addr = CONFIG.load(deps.storage)?.plugin_manager_addr;
+    let plugins = PLUGINS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut suspend_events: Vec<Event> = Vec::new();
+    for (address, mut plugin) in plugins {
+        if plugin.status == PluginStatus::Suspended {
+            continue;
+        }
+
+        let plugin_info: StdResult<PMPluginResponse> =
+            deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: plugin_manager_addr.to_string(),
+                msg: to_json_binary(&PMQueryMsg::PluginInfo {
+                    address: address.to_string(),
+                })?,
+            }));
+
+        match plugin_info {
+            Ok(plugin_info) if plugin_info.enabled && plugin_info.code_id == plugin.code_id => {
+                plugin.max_gas = plugin_info.max_gas;
+            }
+            Err(_) => continue,
+            Ok(_) => {
+                plugin.status = PluginStatus::Suspended;
+                suspend_events.push(
+                    Event::new("plugin_suspended")
+                        .add_attribute("plugin_address", address.to_string())
+                        .add_attribute("reason", "plugin_manager"),
+                );
+            }
+        }
+        PLUGINS.save(deps.storage, &address, &plugin)?;
     }
+
+    if !suspend_events.is_empty() {
+        sync_recovery_policy(deps.storage)?;
+    }
+
+    Ok(suspend_events)
 }
 
 /// Handling contract execution
@@ -73,22 +224,95 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     // check if this is called by this contract itself
-    if info.sender != env.contract.address {
-        return Err(ContractError::Std(StdError::generic_err("Unauthorized")));
+    // guardians are also allowed to freeze and unfreeze this contract,
+    // guardians and recovery plugins are allowed to cancel pending actions
+    let authorized = match msg {
+        ExecuteMsg::Freeze {} | ExecuteMsg::Unfreeze {} => {
+            info.sender == env.contract.address
+                || CONFIG.load(deps.storage)?.guardians.contains(&info.sender)
+        }
+        ExecuteMsg::CancelPendingAction { .. } => {
+            info.sender == env.contract.address
+                || CONFIG.load(deps.storage)?.guardians.contains(&info.sender)
+                || active_recovery_plugins(deps.storage)?
+                    .iter()
+                    .any(|plugin| plugin.contract_address == info.sender)
+        }
+        // active plugins are allowed to execute messages in their scope
+        ExecuteMsg::ExecuteFromPlugin { .. } => PLUGINS
+            .may_load(deps.storage, &info.sender)?
+            .map_or(false, |plugin| plugin.status == PluginStatus::Active),
+        _ => info.sender == env.contract.address,
+    };
+    if !authorized {
+        return Err(ContractError::Unauthorized {});
     }
 
     match msg {
-        ExecuteMsg::RegisterPlugin {
-            plugin_address,
+        ExecuteMsg::RegisterPlugin { .. }
+        | ExecuteMsg::UnregisterPlugin { .. }
+        | ExecuteMsg::UpdatePlugin { .. }
+        | ExecuteMsg::UpdatePluginConfig { .. } => {
+            let mut plugin_infos = PluginInfos::new(deps.storage)?;
+            execute_plugin_op(
+                deps,
+                &env,
+                &mut plugin_infos,
+                msg.plugin_ops().remove(0),
+                info.funds,
+            )
+        }
+        ExecuteMsg::BatchPluginOps { ops } => batch_plugin_ops(deps, env, info, ops),
+        ExecuteMsg::InstallPlugin {
+            code_id,
+            msg,
             config,
-        } => register_plugin(deps, env, info, plugin_address, config),
-        ExecuteMsg::UnregisterPlugin { plugin_address } => {
-            unregister_plugin(deps, env, info, plugin_address)
+            filter,
+            scope,
+        } => install_plugin(deps, env, code_id, msg, config, filter, scope),
+        ExecuteMsg::UpdateRecoveryPolicy { threshold } => {
+            update_recovery_policy(deps, env, info, threshold)
         }
-        ExecuteMsg::UpdatePlugin {
-            plugin_address,
-            status,
-        } => update_plugin(deps, env, info, plugin_address, status),
+        ExecuteMsg::UpdateConfig {
+            plugin_manager_addr,
+            plugin_gas_budget,
+            session_keys_only,
+        } => update_config(
+            deps,
+            env,
+            info,
+            plugin_manager_addr,
+            plugin_gas_budget,
+            session_keys_only,
+        ),
+        ExecuteMsg::RegisterSessionKey {
+            address,
+            expiration,
+            type_urls,
+            contracts,
+            spend_limit,
+        } => register_session_key(
+            deps,
+            env,
+            info,
+            SessionKey {
+                address,
+                expiration,
+                type_urls,
+                contracts,
+                spend_limit,
+                spent: vec![],
+            },
+        ),
+        ExecuteMsg::RevokeSessionKey { address } => revoke_session_key(deps, env, info, address),
+        ExecuteMsg::RevokeAllSessionKeys {} => revoke_all_session_keys(deps, env, info),
+        ExecuteMsg::UpdateGuardians { guardians } => update_guardians(deps, env, info, guardians),
+        ExecuteMsg::Freeze {} => freeze(deps, env, info),
+        ExecuteMsg::Unfreeze {} => unfreeze(deps, env, info),
+        ExecuteMsg::UpdateTimelock { timelock } => update_timelock(deps, env, info, timelock),
+        ExecuteMsg::ExecutePendingAction { id } => execute_pending_action(deps, env, info, id),
+        ExecuteMsg::CancelPendingAction { id } => cancel_pending_action(deps, env, info, id),
+        ExecuteMsg::ExecuteFromPlugin { msgs } => execute_from_plugin(deps, env, info, msgs),
     }
 }
 
@@ -117,127 +341,445 @@ pub fn sudo(deps: DepsMut, env: Env, msg: PyxisSudoMsg) -> Result<Response, Cont
 /// it will call the pre_execute message of all the plugins except the recovery plugin
 /// if any of the plugin returns an error, the whole transaction will be rejected
 pub fn pre_execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     msgs: Vec<SdkMsg>,
     call_info: CallInfo,
     is_authz: bool,
 ) -> Result<Response, ContractError> {
-    // if tx contains UnregisterPlugin or UpdatePlugin messages
+    // if tx unregisters or updates plugins
     // make sure those plugins are not called at this time
+    // messages executed through authz `MsgExec` are checked too
     let mut disable_plugins: Vec<Addr> = Vec::new();
-    for msg in &msgs {
+    let mut timelock = CONFIG.load(deps.storage)?.timelock;
+    for UnwrappedMsg { msg, .. } in unwrap_msgs(&msgs)? {
         if msg.type_url != format!("/{}", MsgExecuteContract::full_name()) {
             continue;
         }
 
-        let msg_exec = MsgExecuteContract::decode(msg.value.as_slice()).unwrap();
+        let msg_exec = MsgExecuteContract::decode(msg.value.as_slice()).map_err(|_| {
+            ContractError::MalformedMessage {
+                type_url: msg.type_url.clone(),
+            }
+        })?;
         if msg_exec.contract == env.contract.address.to_string() {
             // execute call to this smart-account contract must be
             // UnregisterPlugin, RegisterPlugin or UpdatePlugin
             // only smart-account owner can execute those msgs
             // error will be thrown at `after_execute` handler
             // if is_authz {
-            //    return Err(ContractError::Std(StdError::generic_err("Unauthorization")));
+            //    return Err(ContractError::AuthzNotAllowed {});
             // }
 
             let msg_raw: Result<ExecuteMsg, Error> =
                 serde_json_wasm::from_slice(msg_exec.msg.as_slice());
-            if msg_raw.is_err() {
+            let Ok(msg) = msg_raw else {
                 // should never return err in `pre_execute`
                 // if not a message of type `ExecuteMsg` return
                 // in this situation, there will be no need to log error here as it will eventually fail when executing tx
                 return Ok(Response::new().add_attribute("action", "pre_execute"));
-            }
-            let msg = msg_raw.unwrap();
-            match msg {
-                ExecuteMsg::UnregisterPlugin { plugin_address } => {
-                    disable_plugins.push(plugin_address);
-                }
-                ExecuteMsg::UpdatePlugin {
-                    plugin_address,
-                    status: _,
-                } => {
-                    disable_plugins.push(plugin_address);
+            };
+            for op in applied_plugin_ops(deps.storage, msg, &mut timelock)? {
+                match op {
+                    PluginOp::Unregister { plugin_address }
+                    | PluginOp::Update { plugin_address, .. } => {
+                        disable_plugins.push(plugin_address);
+                    }
+                    PluginOp::Register { .. } | PluginOp::UpdateConfig { .. } => {}
                 }
-                _ => {}
             }
         }
     }
 
+    // every transaction gets a new id, passed to the plugins in both hooks
+    let tx_id = TX_COUNTER.may_load(deps.storage)?.unwrap_or_default() + 1;
+    TX_COUNTER.save(deps.storage, &tx_id)?;
+
+    let mut plugins = hook_plugins(deps.as_ref(), &msgs, is_authz, &disable_plugins)?;
+
+    // keep the balances of the tracked denoms to report their changes in `after_execute`
+    let balances = tracked_balances(deps.branch(), &env, &mut plugins)?;
+
     // call the pre_execute message of all the plugins
-    let pre_execute_msgs = PLUGINS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .map(|data| data.unwrap())
-        .filter(|(_, plugin)| {
-            plugin.status == PluginStatus::Active
-                && plugin.plugin_type != PluginType::Recovery
-                && !disable_plugins
-                    .iter()
-                    .any(|addr| addr == &plugin.contract_address)
-        })
-        .map(|(_, plugin)| {
-            CosmosMsg::Wasm(
-                wasm_execute(
-                    &plugin.contract_address,
-                    &PyxisPluginExecuteMsg::PreExecute {
-                        msgs: msgs.clone(),
-                        call_info: call_info.clone(),
-                        is_authz,
-                    },
-                    vec![],
-                )
-                .unwrap(),
-            )
-        })
-        .collect::<Vec<CosmosMsg>>();
+    let mut verified_plugins: Vec<Addr> = Vec::new();
+    let (pre_execute_msgs, suspend_events) =
+        plugin_hook_msgs(deps.branch(), plugins, &mut verified_plugins, |msgs| {
+            PyxisPluginExecuteMsg::PreExecute {
+                msgs,
+                call_info: call_info.clone(),
+                is_authz,
+                tx_id,
+            }
+        })?;
+
+    BALANCE_SNAPSHOT.save(
+        deps.storage,
+        &BalanceSnapshot {
+            tx_id,
+            balances,
+            verified_plugins,
+        },
+    )?;
 
     Ok(Response::new()
         .add_attribute("action", "pre_execute")
-        .add_messages(pre_execute_msgs))
+        .add_attribute("tx_id", tx_id.to_string())
+        .add_submessages(pre_execute_msgs)
+        .add_events(suspend_events))
 }
 
 /// after_execute is called for every message after it is executed
 /// it will call the after_execute message of all the plugins except the recovery plugin
 /// if any of the plugin returns an error, the whole transaction will be rejected
 pub fn after_execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     msgs: Vec<SdkMsg>,
     call_info: CallInfo,
     is_authz: bool,
 ) -> Result<Response, ContractError> {
-    // if tx contains RegisterPlugin, UnregisterPlugin or UpdatePlugin messages
-    // make sure those plugins are not called at this time
+    // reject transactions that the smart account does not allow,
+    // the plugins modified by the transaction are not called at this time
+    let disable_plugins = check_tx(deps.as_ref(), &env, &msgs, is_authz)?;
+
+    // authz transactions must stay in the scope of the session key executing them
+    if is_authz {
+        check_session_key(deps.storage, &env, &msgs, &call_info)?;
+    }
+
+    // the transaction keeps the id given in `pre_execute`,
+    // plugin executions in the transaction may have increased the counter since then
+    let snapshot = BALANCE_SNAPSHOT.may_load(deps.storage)?;
+    BALANCE_SNAPSHOT.remove(deps.storage);
+    let tx_id = match &snapshot {
+        Some(snapshot) => snapshot.tx_id,
+        None => TX_COUNTER.may_load(deps.storage)?.unwrap_or_default(),
+    };
+
+    // the plugins verified in `pre_execute` are not queried again
+    let mut verified_plugins = snapshot
+        .as_ref()
+        .map(|snapshot| snapshot.verified_plugins.clone())
+        .unwrap_or_default();
+
+    // compare the balances of the tracked denoms with the snapshot of `pre_execute`
+    let balance_changes = match snapshot {
+        Some(snapshot) => snapshot
+            .balances
+            .into_iter()
+            .map(|before| -> StdResult<BalanceChange> {
+                let after = deps
+                    .querier
+                    .query_balance(&env.contract.address, &before.denom)?;
+                Ok(BalanceChange {
+                    denom: before.denom,
+                    before: before.amount,
+                    after: after.amount,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?,
+        None => vec![],
+    };
+
+    // plugins executed by the transaction may track other denoms from now on
+    forget_tracked_denoms(deps.storage, &msgs)?;
+
+    // call the after_execute message of all the plugins
+    let plugins = hook_plugins(deps.as_ref(), &msgs, is_authz, &disable_plugins)?;
+    let (after_execute_msgs, suspend_events) =
+        plugin_hook_msgs(deps.branch(), plugins, &mut verified_plugins, |msgs| {
+            PyxisPluginExecuteMsg::AfterExecute {
+                msgs,
+                call_info: call_info.clone(),
+                is_authz,
+                tx_id,
+                balance_changes: balance_changes.clone(),
+            }
+        })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "after_execute")
+        .add_attribute("tx_id", tx_id.to_string())
+        .add_submessages(after_execute_msgs)
+        .add_events(suspend_events))
+}
+
+/// build the hook messages for the plugins returned by `hook_plugins`
+/// every hook message is limited to the max gas declared for the plugin
+/// plugins migrated since their registration are suspended instead of being called,
+/// the code of the plugins in `verified_plugins` is already checked in this transaction
+fn plugin_hook_msgs(
+    mut deps: DepsMut,
+    plugins: Vec<(Plugin, Vec<SdkMsg>)>,
+    verified_plugins: &mut Vec<Addr>,
+    hook_msg: impl Fn(Vec<SdkMsg>) -> PyxisPluginExecuteMsg,
+) -> Result<(Vec<SubMsg>, Vec<Event>), ContractError> {
+    let mut hook_msgs: Vec<SubMsg> = Vec::new();
+    let mut suspend_events: Vec<Event> = Vec::new();
+    for (mut plugin, plugin_msgs) in plugins {
+        if !verified_plugins.contains(&plugin.contract_address) {
+            if let Some(event) = suspend_if_migrated(deps.branch(), &mut plugin)? {
+                suspend_events.push(event);
+                continue;
+            }
+            verified_plugins.push(plugin.contract_address.clone());
+        }
+
+        hook_msgs.push(
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: plugin.contract_address.to_string(),
+                msg: hook_msg(plugin_msgs).to_versioned_binary(plugin.interface_version)?,
+                funds: vec![],
+            })
+            .with_gas_limit(plugin.max_gas),
+        );
+    }
+
+    Ok((hook_msgs, suspend_events))
+}
+
+/// query the balances of the denoms tracked by the plugins called in the hooks of a transaction
+/// the denoms of a plugin are queried with `TrackedDenoms` once and kept until it is executed again,
+/// a plugin which does not answer the `TrackedDenoms` query gets no balance for this call
+/// and is queried again for the next one
+fn tracked_balances(
+    deps: DepsMut,
+    env: &Env,
+    plugins: &mut [(Plugin, Vec<SdkMsg>)],
+) -> Result<Vec<Coin>, ContractError> {
+    let mut denoms: Vec<String> = Vec::new();
+    for (plugin, _) in plugins.iter_mut() {
+        if plugin.interface_version < BALANCE_CHANGES_INTERFACE_VERSION {
+            continue;
+        }
+
+        if plugin.tracked_denoms.is_none() {
+            let Ok(response) = deps.querier.query_wasm_smart::<TrackedDenomsResponse>(
+                &plugin.contract_address,
+                &PyxisPluginQueryMsg::TrackedDenoms {
+                    address: env.contract.address.to_string(),
+                },
+            ) else {
+                continue;
+            };
+            plugin.tracked_denoms = Some(response.denoms);
+            PLUGINS.save(deps.storage, &plugin.contract_address, plugin)?;
+        }
+        for denom in plugin.tracked_denoms.iter().flatten() {
+            if !denoms.contains(denom) {
+                denoms.push(denom.clone());
+            }
+        }
+    }
+
+    let balances = denoms
+        .into_iter()
+        .map(|denom| deps.querier.query_balance(&env.contract.address, denom))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(balances)
+}
+
+/// drop the tracked denoms kept for the plugins executed by a transaction,
+/// they are queried again before the next transaction
+fn forget_tracked_denoms(storage: &mut dyn Storage, msgs: &[SdkMsg]) -> StdResult<()> {
+    for UnwrappedMsg { msg, .. } in unwrap_msgs(msgs)? {
+        if msg.type_url != format!("/{}", MsgExecuteContract::full_name()) {
+            continue;
+        }
+        let Ok(msg_exec) = MsgExecuteContract::decode(msg.value.as_slice()) else {
+            continue;
+        };
+
+        let plugin_address = Addr::unchecked(msg_exec.contract);
+        if let Some(mut plugin) = PLUGINS.may_load(storage, &plugin_address)? {
+            if plugin.tracked_denoms.is_some() {
+                plugin.tracked_denoms = None;
+                PLUGINS.save(storage, &plugin_address, &plugin)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// return the plugins called in a hook with the messages forwarded to each of them
+/// a plugin with a message filter is not called if no message matches,
+/// plugins disabled by the plugin manager are not called
+fn hook_plugins(
+    deps: Deps,
+    msgs: &[SdkMsg],
+    is_authz: bool,
+    disable_plugins: &[Addr],
+) -> Result<Vec<(Plugin, Vec<SdkMsg>)>, ContractError> {
+    let plugins = PLUGINS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut hook_plugins: Vec<(Plugin, Vec<SdkMsg>)> = Vec::new();
+    for (_, plugin) in plugins {
+        if plugin.status != PluginStatus::Active
+            || plugin.plugin_type == PluginType::Recovery
+            || disable_plugins.contains(&plugin.contract_address)
+        {
+            continue;
+        }
+
+        let plugin_msgs = match &plugin.filter {
+            Some(filter) => filter_msgs(filter, msgs, is_authz)?,
+            None => msgs.to_vec(),
+        };
+        if plugin.filter.is_some() && plugin_msgs.is_empty() {
+            continue;
+        }
+
+        hook_plugins.push((plugin, plugin_msgs));
+    }
+
+    if hook_plugins.is_empty() {
+        return Ok(hook_plugins);
+    }
+
+    // ask the plugin manager which plugins are disabled in a single query
+    let addresses: Vec<&Addr> = hook_plugins
+        .iter()
+        .map(|(plugin, _)| &plugin.contract_address)
+        .collect();
+    let Some(disabled_plugins) = plugin_manager_disabled_plugins(deps, &addresses)? else {
+        return Ok(hook_plugins);
+    };
+    if disabled_plugins.paused {
+        return Ok(vec![]);
+    }
+    hook_plugins.retain(|(plugin, _)| {
+        !disabled_plugins
+            .disabled
+            .contains(&plugin.contract_address.to_string())
+    });
+
+    Ok(hook_plugins)
+}
+
+/// ask the plugin manager which of the given plugins are disabled and if all plugins are paused
+/// a plugin manager which can not answer does not disable any plugin
+fn plugin_manager_disabled_plugins(
+    deps: Deps,
+    addresses: &[&Addr],
+) -> StdResult<Option<PMDisabledPluginsResponse>> {
+    let plugin_manager_addr = CONFIG.load(deps.storage)?.plugin_manager_addr;
+    Ok(deps
+        .querier
+        .query_wasm_smart::<PMDisabledPluginsResponse>(
+            plugin_manager_addr,
+            &PMQueryMsg::DisabledPlugins {
+                addresses: addresses
+                    .iter()
+                    .map(|address| address.to_string())
+                    .collect(),
+            },
+        )
+        .ok())
+}
+
+/// suspend a plugin if its contract code is not the one registered to this smart account
+/// a plugin migrated to a code with the registered checksum keeps running the same code
+/// return the event alerting that the plugin is suspended
+fn suspend_if_migrated(deps: DepsMut, plugin: &mut Plugin) -> StdResult<Option<Event>> {
+    let (code_id, registered) = running_code(deps.as_ref(), plugin)?;
+    if registered {
+        if code_id != plugin.code_id {
+            plugin.code_id = code_id;
+            PLUGINS.save(deps.storage, &plugin.contract_address, plugin)?;
+        }
+        return Ok(None);
+    }
+
+    plugin.status = PluginStatus::Suspended;
+    PLUGINS.save(deps.storage, &plugin.contract_address, plugin)?;
+
+    Ok(Some(
+        Event::new("plugin_suspended")
+            .add_attribute("plugin_address", plugin.contract_address.to_string())
+            .add_attribute("reason", "migrated")
+            .add_attribute("registered_code_id", plugin.code_id.to_string())
+            .add_attribute("code_id", code_id.to_string()),
+    ))
+}
+
+/// the code id a plugin is running and whether it is the code registered to this smart account,
+/// a code with the registered checksum counts as the registered code
+fn running_code(deps: Deps, plugin: &Plugin) -> StdResult<(u64, bool)> {
+    let contract_info: ContractInfoResponse =
+        deps.querier
+            .query(&QueryRequest::Wasm(WasmQuery::ContractInfo {
+                contract_addr: plugin.contract_address.to_string(),
+            }))?;
+    if contract_info.code_id == plugin.code_id {
+        return Ok((contract_info.code_id, true));
+    }
+
+    let Some(checksum) = &plugin.checksum else {
+        return Ok((contract_info.code_id, false));
+    };
+    let code_checksum = deps
+        .querier
+        .query::<CodeInfoResponse>(&QueryRequest::Wasm(WasmQuery::CodeInfo {
+            code_id: contract_info.code_id,
+        }))
+        .map(|code_info| code_info.checksum)
+        .ok();
+
+    Ok((
+        contract_info.code_id,
+        code_checksum.as_ref() == Some(checksum),
+    ))
+}
+
+/// check the rules of this smart account on the messages of a transaction in `after_execute`
+/// return the plugins registered, unregistered or updated by the transaction
+fn check_tx(
+    deps: Deps,
+    env: &Env,
+    msgs: &[SdkMsg],
+    is_authz: bool,
+) -> Result<Vec<Addr>, ContractError> {
+    // a frozen smart account only executes the messages unfreezing it
+    if CONFIG.load(deps.storage)?.frozen && !is_unfreeze_tx(deps.storage, env, msgs, is_authz)? {
+        return Err(ContractError::AccountFrozen {});
+    }
+
+    // if tx registers, unregisters or updates plugins
+    // return those plugins so that they are not called at this time
+    // messages executed through authz `MsgExec` are checked too
     let mut disable_plugins: Vec<Addr> = Vec::new();
-    for msg in &msgs {
+    let mut timelock = CONFIG.load(deps.storage)?.timelock;
+    for UnwrappedMsg { msg, nested } in unwrap_msgs(msgs)? {
         if msg.type_url != format!("/{}", MsgExecuteContract::full_name()) {
             continue;
         }
 
-        let msg_exec = MsgExecuteContract::decode(msg.value.as_slice()).unwrap();
+        let msg_exec = MsgExecuteContract::decode(msg.value.as_slice()).map_err(|_| {
+            ContractError::MalformedMessage {
+                type_url: msg.type_url.clone(),
+            }
+        })?;
         let msg_contract_addr = Addr::unchecked(msg_exec.contract);
         if let Some(plugin) = PLUGINS.may_load(deps.storage, &msg_contract_addr)? {
             // do not allow smart account to directly execute `PyxisPlugin Execute` messages
             // those messages are only called on behalf of the account through the smart contract
-            match plugin.plugin_type {
-                PluginType::Recovery => {
-                    let msg: Result<PyxisRecoveryPluginExecuteMsg, _> =
-                        serde_json_wasm::from_slice(msg_exec.msg.as_slice());
-                    if msg.is_ok() {
-                        return Err(ContractError::Std(StdError::generic_err(
-                            "Not allowed action",
-                        )));
-                    }
-                }
-                PluginType::Other => {
-                    let msg: Result<PyxisPluginExecuteMsg, _> =
-                        serde_json_wasm::from_slice(msg_exec.msg.as_slice());
-                    if msg.is_ok() {
-                        return Err(ContractError::Std(StdError::generic_err(
-                            "Not allowed action",
-                        )));
-                    }
+            // every version of the plugin interface is matched by the name of the message
+            let reserved_msgs = match plugin.plugin_type {
+                PluginType::Recovery => RECOVERY_PLUGIN_EXECUTE_MSGS.as_slice(),
+                PluginType::Other => PLUGIN_EXECUTE_MSGS.as_slice(),
+            };
+            let msg_name: Result<BTreeMap<String, IgnoredAny>, _> =
+                serde_json_wasm::from_slice(msg_exec.msg.as_slice());
+            if let Ok(msg_name) = msg_name {
+                if msg_name
+                    .keys()
+                    .any(|name| reserved_msgs.contains(&name.as_str()))
+                {
+                    return Err(ContractError::NotAllowedAction {});
                 }
             }
         }
@@ -245,285 +787,1028 @@ pub fn after_execute(
         if msg_contract_addr == env.contract.address {
             // execute call to this smart-account contract must be
             // UnregisterPlugin, RegisterPlugin or UpdatePlugin
-            // only smart-account owner can execute those msgs
-            if is_authz {
-                return Err(ContractError::Std(StdError::generic_err("Unauthorization")));
+            // only smart-account owner can execute those msgs, not through authz
+            if is_authz || nested {
+                return Err(ContractError::AuthzNotAllowed {});
             }
 
-            let msg: ExecuteMsg = serde_json_wasm::from_slice(msg_exec.msg.as_slice()).unwrap();
-            match msg {
-                ExecuteMsg::RegisterPlugin {
-                    plugin_address,
-                    config: _,
-                } => {
-                    disable_plugins.push(plugin_address);
-                }
-                ExecuteMsg::UnregisterPlugin { plugin_address } => {
-                    disable_plugins.push(plugin_address);
-                }
-                ExecuteMsg::UpdatePlugin {
-                    plugin_address,
-                    status: _,
-                } => {
-                    disable_plugins.push(plugin_address);
+            let msg: ExecuteMsg =
+                serde_json_wasm::from_slice(msg_exec.msg.as_slice()).map_err(|_| {
+                    ContractError::MalformedMessage {
+                        type_url: msg.type_url.clone(),
+                    }
+                })?;
+            // the instance installed by the message is registered as well
+            if let ExecuteMsg::InstallPlugin { code_id, .. } = msg {
+                disable_plugins.push(plugin_instance_address(deps, env, code_id)?);
+            }
+            for op in applied_plugin_ops(deps.storage, msg, &mut timelock)? {
+                match op {
+                    PluginOp::Register { plugin_address, .. }
+                    | PluginOp::Unregister { plugin_address }
+                    | PluginOp::Update { plugin_address, .. }
+                    | PluginOp::UpdateConfig { plugin_address, .. } => {
+                        disable_plugins.push(plugin_address);
+                    }
                 }
             }
         }
     }
 
-    // call the pre_execute message of all the plugins
-    let after_execute_msgs = PLUGINS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .map(|data| data.unwrap())
-        .filter(|(_, plugin)| {
-            plugin.status == PluginStatus::Active
-                && plugin.plugin_type != PluginType::Recovery
-                && !disable_plugins
-                    .iter()
-                    .any(|addr| addr == &plugin.contract_address)
-        })
-        .map(|(_, plugin)| {
-            CosmosMsg::Wasm(
-                wasm_execute(
-                    &plugin.contract_address,
-                    &PyxisPluginExecuteMsg::AfterExecute {
-                        msgs: msgs.clone(),
-                        call_info: call_info.clone(),
-                        is_authz,
-                    },
-                    vec![],
-                )
-                .unwrap(),
-            )
-        })
-        .collect::<Vec<CosmosMsg>>();
+    Ok(disable_plugins)
+}
 
-    Ok(Response::new()
-        .add_attribute("action", "after_execute")
-        .add_messages(after_execute_msgs))
+/// check if every message of a transaction unfreezes this smart account,
+/// either with `Unfreeze` or by executing a pending `Unfreeze` action
+/// authz transactions can not unfreeze this smart account
+fn is_unfreeze_tx(
+    storage: &dyn Storage,
+    env: &Env,
+    msgs: &[SdkMsg],
+    is_authz: bool,
+) -> StdResult<bool> {
+    if is_authz || msgs.is_empty() {
+        return Ok(false);
+    }
+
+    for msg in msgs {
+        if msg.type_url != format!("/{}", MsgExecuteContract::full_name()) {
+            return Ok(false);
+        }
+        let Ok(msg_exec) = MsgExecuteContract::decode(msg.value.as_slice()) else {
+            return Ok(false);
+        };
+        if msg_exec.contract != env.contract.address.as_str() {
+            return Ok(false);
+        }
+        let unfreezes = match serde_json_wasm::from_slice::<ExecuteMsg>(msg_exec.msg.as_slice()) {
+            Ok(ExecuteMsg::Unfreeze {}) => true,
+            Ok(ExecuteMsg::ExecutePendingAction { id }) => matches!(
+                PENDING_ACTIONS.may_load(storage, id)?,
+                Some(PendingAction {
+                    action: SensitiveAction::Unfreeze {},
+                    ..
+                })
+            ),
+            _ => false,
+        };
+        if !unfreezes {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// return the messages that match a plugin's message filter
+fn filter_msgs(
+    filter: &MessageFilter,
+    msgs: &[SdkMsg],
+    is_authz: bool,
+) -> Result<Vec<SdkMsg>, ContractError> {
+    match filter.call_type {
+        CallType::Direct if is_authz => return Ok(vec![]),
+        CallType::Authz if !is_authz => return Ok(vec![]),
+        _ => {}
+    }
+
+    let mut matched_msgs: Vec<SdkMsg> = Vec::new();
+    for msg in msgs {
+        if !filter.type_urls.is_empty() && !filter.type_urls.contains(&msg.type_url) {
+            continue;
+        }
+
+        // contract addresses only apply to `MsgExecuteContract` messages
+        if !filter.contracts.is_empty()
+            && msg.type_url == format!("/{}", MsgExecuteContract::full_name())
+        {
+            let msg_exec = MsgExecuteContract::decode(msg.value.as_slice()).map_err(|_| {
+                ContractError::MalformedMessage {
+                    type_url: msg.type_url.clone(),
+                }
+            })?;
+            if !filter
+                .contracts
+                .iter()
+                .any(|contract| contract.as_str() == msg_exec.contract)
+            {
+                continue;
+            }
+        }
+
+        matched_msgs.push(msg.clone());
+    }
+
+    Ok(matched_msgs)
 }
 
 /// handle_recover is called when a smart account is recovered (change owner)
-/// it will call the recover message of the recovery plugin
-/// if the recovery plugin returns an error, the whole transaction will be rejected
+/// it will call the recover message of every active recovery plugin
+/// the recovery succeeds only if the number of approving recovery plugins reaches
+/// the threshold of the recovery policy, otherwise the whole transaction will be rejected
 /// if there is no recovery plugin, the transaction will be rejected
 pub fn handle_recover(
-    deps: DepsMut,
+    mut deps: DepsMut,
     _env: Env,
     caller: String,
     pub_key: Binary,
     credentials: Binary,
 ) -> Result<Response, ContractError> {
+    // migrated recovery plugins must not take part in the recovery
+    let mut suspend_events: Vec<Event> = Vec::new();
+    for mut plugin in active_recovery_plugins(deps.storage)? {
+        if let Some(event) = suspend_if_migrated(deps.branch(), &mut plugin)? {
+            suspend_events.push(event);
+        }
+    }
+    if !suspend_events.is_empty() {
+        sync_recovery_policy(deps.storage)?;
+    }
+
     // recover is only enabled after a recovery plugin is registered
-    // we also limit the recovery plugin to only one
     // load config to check if recoverable is enabled
     let config = CONFIG.load(deps.storage)?;
-    if !config.recoverable {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Recovery is not enabled",
-        )));
-    }
-
-    // we will loop through every plugin to find the recovery plugin
-    // and pass the recover message to it
-    let recover_msgs = PLUGINS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .map(|data| data.unwrap())
-        .filter(|(_, plugin)| {
-            plugin.status == PluginStatus::Active && plugin.plugin_type == PluginType::Recovery
-        })
-        .map(|(_, plugin)| {
-            CosmosMsg::Wasm(
-                wasm_execute(
-                    &plugin.contract_address,
-                    &PyxisRecoveryPluginExecuteMsg::Recover {
+    let policy = match config.recoverable {
+        Some(policy) => policy,
+        None => return Err(ContractError::RecoveryNotEnabled {}),
+    };
+
+    let recovery_plugins = active_recovery_plugins(deps.storage)?;
+    if (recovery_plugins.len() as u32) < policy.threshold {
+        return Err(ContractError::InvalidRecoveryThreshold {
+            threshold: policy.threshold,
+            recovery_plugins: recovery_plugins.len() as u32,
+        });
+    }
+
+    // the nonce is passed to the recovery plugins so that credentials can not be replayed
+    // legacy recovery plugins do not receive it, they are flagged by the `RecoveryStatus` query
+    let nonce = RECOVERY_NONCE.may_load(deps.storage)?.unwrap_or_default();
+
+    // every recovery plugin is called in a submessage
+    // a successful submessage is counted as an approval in `handle_recover_reply`
+    let recover_msgs = recovery_plugins
+        .iter()
+        .map(|plugin| -> StdResult<SubMsg> {
+            Ok(SubMsg::reply_always(
+                WasmMsg::Execute {
+                    contract_addr: plugin.contract_address.to_string(),
+                    msg: PyxisRecoveryPluginExecuteMsg::Recover {
                         caller: caller.clone(),
                         pub_key: pub_key.clone(),
                         credentials: credentials.clone(),
-                    },
-                    vec![],
-                )
-                .unwrap(),
+                        nonce,
+                    }
+                    .to_versioned_binary(plugin.interface_version)?,
+                    funds: vec![],
+                },
+                RECOVER_REPLY_ID,
             )
+            .with_gas_limit(plugin.max_gas))
         })
-        .collect::<Vec<CosmosMsg>>();
+        .collect::<StdResult<Vec<SubMsg>>>()?;
 
-    assert!(
-        recover_msgs.len() == 1,
-        "There should be only one recovery plugin"
-    );
+    RECOVERY_TALLY.save(
+        deps.storage,
+        &RecoveryTally {
+            threshold: policy.threshold,
+            nonce,
+            caller,
+            pub_key_hash: Sha256::digest(pub_key.as_slice()).to_vec().into(),
+            pending: recovery_plugins
+                .into_iter()
+                .map(|plugin| plugin.contract_address)
+                .collect(),
+            approvals: vec![],
+        },
+    )?;
 
     Ok(Response::new()
         .add_attribute("action", "recover")
-        .add_messages(recover_msgs))
+        .add_attribute("threshold", policy.threshold.to_string())
+        .add_attribute("nonce", nonce.to_string())
+        .add_submessages(recover_msgs)
+        .add_events(suspend_events))
 }
 
-/// Register a plugin to this smart account
-/// Only this smart account can register a plugin for itself
-pub fn register_plugin(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    plugin_address: Addr,
-    config: String,
-) -> Result<Response, ContractError> {
-    // check if this plugin has already been registered
-    // for now we will throw error
-    if PLUGINS.has(deps.storage, &plugin_address) {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Plugin is already registered",
-        )));
+/// handle_recover_reply counts the result of a recovery plugin
+/// when every recovery plugin has replied, the recovery is rejected
+/// if the number of approvals is lower than the threshold
+/// a successful recovery is recorded in the recovery log and increases the recovery nonce
+fn handle_recover_reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let mut tally = RECOVERY_TALLY.load(deps.storage)?;
+    if tally.pending.is_empty() {
+        return Err(ContractError::NoPendingRecovery {});
     }
 
-    // call plugin manager to check if this plugin is valid
-    // if the request is successful, it means the plugin is valid
-    let plugin_manager_addr = CONFIG.load(deps.storage)?.plugin_manager_addr;
-    let query_plugin_msg = PMQueryMsg::PluginInfo {
-        address: plugin_address.to_string(),
-    };
-    let plugin_info: PluginResponse =
-        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: plugin_manager_addr.to_string(),
-            msg: to_json_binary(&query_plugin_msg)?,
-        }))?;
+    // replies are received in the same order as the submessages are dispatched
+    let plugin_address = tally.pending.remove(0);
+    let approved = msg.result.is_ok();
+    if approved {
+        tally.approvals.push(plugin_address.clone());
+    }
 
-    // check if plugin is enable
-    if !plugin_info.enabled {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Plugin is disabled",
-        )));
+    let response = Response::new()
+        .add_attribute("action", "recover_reply")
+        .add_attribute("plugin_address", plugin_address)
+        .add_attribute("approved", approved.to_string());
+
+    if !tally.pending.is_empty() {
+        RECOVERY_TALLY.save(deps.storage, &tally)?;
+        return Ok(response);
     }
 
-    // query plugin contract infor
-    let contract_info: ContractInfoResponse =
-        deps.querier
-            .query(&QueryRequest::Wasm(WasmQuery::ContractInfo {
-                contract_addr: plugin_info.address.to_string(),
-            }))?;
-    // check if plugin contract not been migrated
-    if contract_info.code_id != plugin_info.code_id {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Invalid plugin code_id",
-        )));
+    RECOVERY_TALLY.remove(deps.storage);
+    if (tally.approvals.len() as u32) < tally.threshold {
+        return Err(ContractError::RecoveryThresholdNotReached {
+            approvals: tally.approvals.len() as u32,
+            threshold: tally.threshold,
+        });
     }
 
-    // add this plugin and its config to the storage
-    PLUGINS.save(
+    RECOVERY_LOG.save(
         deps.storage,
-        &plugin_address.clone(),
-        &Plugin {
-            name: plugin_info.name,
-            plugin_type: plugin_info.plugin_type.clone(),
-            contract_address: plugin_address.clone(),
-            status: PluginStatus::Active,
+        tally.nonce,
+        &RecoveryRecord {
+            nonce: tally.nonce,
+            height: env.block.height,
+            caller: tally.caller,
+            pub_key_hash: tally.pub_key_hash,
+            plugins: tally.approvals.clone(),
+        },
+    )?;
+    RECOVERY_NONCE.save(deps.storage, &(tally.nonce + 1))?;
+
+    // pending actions may have been queued with the lost key
+    let pending_action_ids = PENDING_ACTIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for id in pending_action_ids {
+        PENDING_ACTIONS.remove(deps.storage, id);
+    }
+
+    // a recovered smart account is not frozen anymore
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.frozen {
+        config.frozen = false;
+        CONFIG.save(deps.storage, &config)?;
+        UNFREEZE_APPROVALS.remove(deps.storage);
+    }
+
+    Ok(response
+        .add_attribute("approvals", tally.approvals.len().to_string())
+        .add_attribute("frozen", "false"))
+}
+
+/// Execute plugin operations atomically
+/// Only this smart account can execute plugin operations for itself
+/// the plugin manager is queried once for each plugin of the batch
+fn batch_plugin_ops(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ops: Vec<PluginOp>,
+) -> Result<Response, ContractError> {
+    if ops.is_empty() {
+        return Err(ContractError::EmptyPluginOps {});
+    }
+    // funds can not be split between the operations
+    if !info.funds.is_empty() {
+        return Err(ContractError::BatchFundsNotAccepted {});
+    }
+
+    let mut plugin_infos = PluginInfos::new(deps.storage)?;
+    let mut response = Response::new()
+        .add_attribute("action", "batch_plugin_ops")
+        .add_attribute("ops", ops.len().to_string());
+    for op in ops {
+        let op_response = execute_plugin_op(deps.branch(), &env, &mut plugin_infos, op, vec![])?;
+        response = response
+            .add_submessages(op_response.messages)
+            .add_attributes(op_response.attributes)
+            .add_events(op_response.events);
+    }
+
+    Ok(response)
+}
+
+fn execute_plugin_op(
+    deps: DepsMut,
+    env: &Env,
+    plugin_infos: &mut PluginInfos,
+    op: PluginOp,
+    funds: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    // sensitive operations wait for the timelock of this smart account
+    let timelock = CONFIG.load(deps.storage)?.timelock;
+    if timelock > 0 {
+        if let Some(action) = sensitive_plugin_action(&op) {
+            return queue_action(deps, env, timelock, action);
+        }
+    }
+
+    match op {
+        PluginOp::Register {
+            plugin_address,
+            config,
+            filter,
+            scope,
+        } => register_plugin(
+            deps,
+            env,
+            plugin_infos,
+            plugin_address,
+            config,
+            filter,
+            scope,
+            funds,
+        ),
+        PluginOp::Unregister { plugin_address } => {
+            unregister_plugin(deps, env, plugin_infos, plugin_address, funds)
+        }
+        PluginOp::Update {
+            plugin_address,
+            status,
+        } => update_plugin(deps, env, plugin_infos, plugin_address, status),
+        PluginOp::UpdateConfig {
+            plugin_address,
+            config,
+        } => update_plugin_config(deps, env, plugin_address, config, funds),
+    }
+}
+
+/// return the pending action of a plugin operation delayed by the timelock of this smart account
+fn sensitive_plugin_action(op: &PluginOp) -> Option<SensitiveAction> {
+    match op {
+        PluginOp::Unregister { plugin_address } => Some(SensitiveAction::UnregisterPlugin {
+            plugin_address: plugin_address.clone(),
+        }),
+        PluginOp::Update {
+            plugin_address,
+            status: PluginStatus::Inactive,
+        } => Some(SensitiveAction::DeactivatePlugin {
+            plugin_address: plugin_address.clone(),
+        }),
+        PluginOp::UpdateConfig {
+            plugin_address,
+            config,
+        } => Some(SensitiveAction::UpdatePluginConfig {
+            plugin_address: plugin_address.clone(),
             config: config.clone(),
+        }),
+        PluginOp::Register { .. } | PluginOp::Update { .. } => None,
+    }
+}
+
+/// return the plugin operations applied by a message of a transaction to this smart account
+/// operations delayed by the timelock only modify their plugin when their pending action
+/// is executed, `timelock` follows the timelock raised by the previous messages of the transaction
+fn applied_plugin_ops(
+    storage: &dyn Storage,
+    msg: ExecuteMsg,
+    timelock: &mut u64,
+) -> StdResult<Vec<PluginOp>> {
+    match msg {
+        // raising the timelock applies immediately, lowering it is queued
+        ExecuteMsg::UpdateTimelock {
+            timelock: new_timelock,
+        } => {
+            *timelock = (*timelock).max(new_timelock);
+            Ok(vec![])
+        }
+        ExecuteMsg::ExecutePendingAction { id } => {
+            let action = PENDING_ACTIONS
+                .may_load(storage, id)?
+                .map(|pending_action| pending_action.action);
+            Ok(match action {
+                Some(SensitiveAction::UnregisterPlugin { plugin_address }) => {
+                    vec![PluginOp::Unregister { plugin_address }]
+                }
+                Some(SensitiveAction::DeactivatePlugin { plugin_address }) => {
+                    vec![PluginOp::Update {
+                        plugin_address,
+                        status: PluginStatus::Inactive,
+                    }]
+                }
+                Some(SensitiveAction::UpdatePluginConfig {
+                    plugin_address,
+                    config,
+                }) => vec![PluginOp::UpdateConfig {
+                    plugin_address,
+                    config,
+                }],
+                _ => vec![],
+            })
+        }
+        msg => Ok(msg
+            .plugin_ops()
+            .into_iter()
+            .filter(|op| *timelock == 0 || sensitive_plugin_action(op).is_none())
+            .collect()),
+    }
+}
+
+/// PluginInfos keeps the answers of the plugin manager during an execution
+/// so that each plugin is queried only once
+struct PluginInfos {
+    plugin_manager_addr: Addr,
+    // None if the plugin manager does not know the plugin
+    infos: Vec<(Addr, Option<PMPluginResponse>)>,
+}
+
+impl PluginInfos {
+    fn new(storage: &dyn Storage) -> StdResult<Self> {
+        Ok(PluginInfos {
+            plugin_manager_addr: CONFIG.load(storage)?.plugin_manager_addr,
+            infos: vec![],
+        })
+    }
+
+    fn get(&mut self, querier: &QuerierWrapper, plugin_address: &Addr) -> Option<PMPluginResponse> {
+        if let Some((_, info)) = self.infos.iter().find(|(addr, _)| addr == plugin_address) {
+            return info.clone();
+        }
+
+        let info: Option<PMPluginResponse> = to_json_binary(&PMQueryMsg::PluginInfo {
+            address: plugin_address.to_string(),
+        })
+        .and_then(|msg| {
+            querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: self.plugin_manager_addr.to_string(),
+                msg,
+            }))
+        })
+        .ok();
+        self.infos.push((plugin_address.clone(), info.clone()));
+
+        info
+    }
+}
+
+/// Register a plugin to this smart account
+/// Only this smart account can register a plugin for itself
+#[allow(clippy::too_many_arguments)]
+fn register_plugin(
+    deps: DepsMut,
+    _env: &Env,
+    plugin_infos: &mut PluginInfos,
+    plugin_address: Addr,
+    plugin_config: String,
+    filter: Option<MessageFilter>,
+    scope: Option<PluginScope>,
+    funds: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    // check if this plugin has already been registered
+    // a suspended plugin can be registered again to be used with its new code
+    if let Some(plugin) = PLUGINS.may_load(deps.storage, &plugin_address)? {
+        if plugin.status != PluginStatus::Suspended {
+            return Err(ContractError::PluginAlreadyRegistered {});
+        }
+    }
+
+    // call plugin manager to check if this plugin is valid
+    // if the request is successful, it means the plugin is valid
+    let config = CONFIG.load(deps.storage)?;
+    let plugin_info = plugin_infos
+        .get(&deps.querier, &plugin_address)
+        .ok_or(ContractError::PluginNotAllowed {})?;
+
+    // check if plugin is enable
+    if !plugin_info.enabled {
+        return Err(ContractError::PluginDisabled {});
+    }
+
+    // no plugin can be registered while the plugin manager pauses all plugins
+    if matches!(
+        plugin_manager_disabled_plugins(deps.as_ref(), &[&plugin_address])?,
+        Some(PMDisabledPluginsResponse { paused: true, .. })
+    ) {
+        return Err(ContractError::PluginDisabled {});
+    }
+
+    // query plugin contract infor
+    let contract_info: ContractInfoResponse =
+        deps.querier
+            .query(&QueryRequest::Wasm(WasmQuery::ContractInfo {
+                contract_addr: plugin_info.address.to_string(),
+            }))?;
+    // check if plugin contract not been migrated
+    if contract_info.code_id != plugin_info.code_id {
+        return Err(ContractError::CodeIdMismatch {
+            expected: plugin_info.code_id,
+            actual: contract_info.code_id,
+        });
+    }
+
+    // plugins called in `pre_execute` and `after_execute` must fit in the gas budget of this smart account
+    if plugin_info.max_gas == 0 {
+        return Err(ContractError::PluginMaxGasNotDeclared {});
+    }
+    if plugin_info.plugin_type != PluginType::Recovery {
+        let used_gas = PLUGINS
+            .range(deps.storage, None, None, Order::Ascending)
+            .try_fold(0u64, |total, item| -> StdResult<u64> {
+                let (address, plugin) = item?;
+                match plugin.plugin_type {
+                    // a suspended plugin registered again replaces its previous registration
+                    _ if address == plugin_address => Ok(total),
+                    PluginType::Recovery => Ok(total),
+                    PluginType::Other => Ok(total.saturating_add(plugin.max_gas)),
+                }
+            })?;
+        if used_gas.saturating_add(plugin_info.max_gas) > config.plugin_gas_budget {
+            return Err(ContractError::PluginGasBudgetExceeded {
+                required: used_gas.saturating_add(plugin_info.max_gas),
+                budget: config.plugin_gas_budget,
+            });
+        }
+    }
+
+    // hook messages are encoded in the version of the plugin interface supported by the plugin
+    let interface_version =
+        plugin_interface_version(&deps.querier, &plugin_address, &plugin_info.plugin_type)?;
+
+    // recovery plugins must receive the recovery nonce so that their approvals can not be replayed
+    if plugin_info.plugin_type == PluginType::Recovery
+        && interface_version < RECOVERY_NONCE_INTERFACE_VERSION
+    {
+        return Err(ContractError::LegacyRecoveryPlugin {
+            version: interface_version,
+        });
+    }
+
+    // keep the checksum of the plugin code if the chain supports the code info query
+    let checksum = deps
+        .querier
+        .query::<CodeInfoResponse>(&QueryRequest::Wasm(WasmQuery::CodeInfo {
+            code_id: contract_info.code_id,
+        }))
+        .map(|code_info| code_info.checksum)
+        .ok();
+
+    // contracts of the message filter must be valid addresses
+    if let Some(filter) = &filter {
+        for contract in &filter.contracts {
+            deps.api.addr_validate(contract.as_str())?;
+        }
+    }
+
+    // recipients of the plugin scope must be valid addresses
+    if let Some(scope) = &scope {
+        for recipient in &scope.recipients {
+            deps.api.addr_validate(recipient.as_str())?;
+        }
+    }
+
+    // add this plugin and its config to the storage
+    PLUGINS.save(
+        deps.storage,
+        &plugin_address.clone(),
+        &Plugin {
+            name: plugin_info.name,
+            plugin_type: plugin_info.plugin_type.clone(),
+            contract_address: plugin_address.clone(),
+            status: PluginStatus::Active,
+            config: plugin_config.clone(),
+            filter,
+            max_gas: plugin_info.max_gas,
+            code_id: contract_info.code_id,
+            checksum,
+            interface_version,
+            scope,
+            tracked_denoms: None,
         },
     )?;
 
-    // TODO: may allow multiple recovery plugins in the future
-    // if plugin type is recovery and recoverable is false, enable recoverable
-    // otherwise, throw an error
+    // the first recovery plugin enables recovery with a threshold of one
+    // the threshold can be raised later with `UpdateRecoveryPolicy`
     if plugin_info.plugin_type == PluginType::Recovery {
-        let mut config = CONFIG.load(deps.storage)?;
-        if !config.recoverable {
-            config.recoverable = true;
-            CONFIG.save(deps.storage, &config)?;
-        } else {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Recovery plugin is already registered",
-            )));
-        }
+        sync_recovery_policy(deps.storage)?;
     }
 
     let register_msg = CosmosMsg::Wasm(wasm_execute(
         plugin_address.as_str(),
-        &PyxisPluginExecuteMsg::Register { config },
-        info.funds,
+        &PyxisPluginExecuteMsg::Register {
+            config: plugin_config,
+        },
+        funds,
     )?);
 
     Ok(Response::new()
         .add_attribute("action", "register")
-        .add_message(register_msg))
+        .add_message(register_msg)
+        .add_submessage(notify_plugin_manager(
+            &config.plugin_manager_addr,
+            &PMExecuteMsg::PluginRegistered {
+                plugin_address: plugin_address.to_string(),
+            },
+        )?))
+}
+
+/// Instantiate a plugin dedicated to this smart account from a template of the plugin manager
+/// the plugin manager is the admin of the instance and allows it like any other plugin,
+/// then the instance is registered with `RegisterPlugin`
+fn install_plugin(
+    deps: DepsMut,
+    env: Env,
+    code_id: u64,
+    msg: Binary,
+    config: String,
+    filter: Option<MessageFilter>,
+    scope: Option<PluginScope>,
+) -> Result<Response, ContractError> {
+    let plugin_manager_addr = CONFIG.load(deps.storage)?.plugin_manager_addr;
+    let template: PMPluginTemplateResponse = deps
+        .querier
+        .query_wasm_smart(
+            plugin_manager_addr.to_string(),
+            &PMQueryMsg::PluginTemplate { code_id },
+        )
+        .map_err(|_| ContractError::PluginNotAllowed {})?;
+    if !template.enabled {
+        return Err(ContractError::PluginDisabled {});
+    }
+
+    let plugin_address = plugin_instance_address(deps.as_ref(), &env, code_id)?;
+    let mut response = Response::new()
+        .add_attribute("action", "install_plugin")
+        .add_attribute("plugin_address", plugin_address.to_string());
+
+    // the instance of a template installed before is registered again
+    let instantiated = deps
+        .querier
+        .query::<ContractInfoResponse>(&QueryRequest::Wasm(WasmQuery::ContractInfo {
+            contract_addr: plugin_address.to_string(),
+        }))
+        .is_ok();
+    if !instantiated {
+        response = response
+            .add_message(WasmMsg::Instantiate2 {
+                admin: Some(plugin_manager_addr.to_string()),
+                code_id,
+                label: format!("{} plugin of {}", template.name, env.contract.address),
+                msg,
+                funds: vec![],
+                salt: plugin_instance_salt(code_id),
+            })
+            .add_message(wasm_execute(
+                plugin_manager_addr.as_str(),
+                &PMExecuteMsg::PluginInstantiated {
+                    plugin_address: plugin_address.to_string(),
+                },
+                vec![],
+            )?);
+    }
+
+    Ok(response.add_message(wasm_execute(
+        env.contract.address.as_str(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address,
+            config,
+            filter,
+            scope,
+        },
+        vec![],
+    )?))
+}
+
+/// the instantiate2 salt of the instance of a plugin template
+/// the salt only depends on the template, so a smart account has a single instance of each template:
+/// installing the template again registers that instance and ignores the new instantiate message
+fn plugin_instance_salt(code_id: u64) -> Binary {
+    Binary::from(code_id.to_be_bytes().to_vec())
+}
+
+/// the address of the instance of a plugin template installed by this smart account
+fn plugin_instance_address(deps: Deps, env: &Env, code_id: u64) -> Result<Addr, ContractError> {
+    let checksum = deps.querier.query_wasm_code_info(code_id)?.checksum;
+    let creator = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+
+    let address = instantiate2_address(
+        checksum.as_slice(),
+        &creator,
+        plugin_instance_salt(code_id).as_slice(),
+    )
+    .map_err(ContractError::Instantiate2Address)?;
+
+    Ok(deps.api.addr_humanize(&address)?)
+}
+
+/// Notify the plugin manager that this smart account registered or unregistered a plugin
+/// the notification is best-effort, a plugin manager which fails to handle it does not revert the transaction
+fn notify_plugin_manager(
+    plugin_manager_addr: &Addr,
+    msg: &PMExecuteMsg,
+) -> Result<SubMsg, ContractError> {
+    Ok(SubMsg::reply_on_error(
+        wasm_execute(plugin_manager_addr.as_str(), msg, vec![])?,
+        NOTIFY_PLUGIN_MANAGER_REPLY_ID,
+    ))
+}
+
+/// Execute messages on behalf of this smart account, called by an active plugin
+/// the messages must stay in the scope given to the plugin at registration
+/// the hooks of the other plugins are called before and after the messages
+fn execute_from_plugin(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msgs: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    if CONFIG.load(deps.storage)?.frozen {
+        return Err(ContractError::AccountFrozen {});
+    }
+
+    // plugins disabled or paused by the plugin manager can not execute messages either
+    if let Some(disabled_plugins) = plugin_manager_disabled_plugins(deps.as_ref(), &[&info.sender])?
+    {
+        if disabled_plugins.paused || disabled_plugins.disabled.contains(&info.sender.to_string()) {
+            return Err(ContractError::PluginDisabled {});
+        }
+    }
+
+    let plugin = PLUGINS.load(deps.storage, &info.sender)?;
+    let scope = plugin.scope.ok_or(ContractError::PluginScopeExceeded {
+        plugin_address: info.sender.to_string(),
+    })?;
+    let sdk_msgs = scoped_sdk_msgs(deps.as_ref(), &env, &info.sender, &scope, &msgs)?;
+
+    // the execution is a transaction of its own for the hooks of the other plugins
+    let tx_id = TX_COUNTER.may_load(deps.storage)?.unwrap_or_default() + 1;
+    TX_COUNTER.save(deps.storage, &tx_id)?;
+
+    // plugins executed by the messages may track other denoms from now on
+    forget_tracked_denoms(deps.storage, &sdk_msgs)?;
+
+    let disable_plugins = vec![info.sender.clone()];
+    let mut plugins = hook_plugins(deps.as_ref(), &sdk_msgs, false, &disable_plugins)?;
+    let call_info = CallInfo::default();
+    let mut verified_plugins: Vec<Addr> = Vec::new();
+    let (pre_execute_msgs, pre_execute_events) = plugin_hook_msgs(
+        deps.branch(),
+        plugins.clone(),
+        &mut verified_plugins,
+        |msgs| PyxisPluginExecuteMsg::PreExecute {
+            msgs,
+            call_info: call_info.clone(),
+            is_authz: false,
+            tx_id,
+        },
+    )?;
+    // balance changes are not reported, the messages are executed after this call
+    // plugins suspended in the first hook are not called again
+    plugins.retain(|(plugin, _)| verified_plugins.contains(&plugin.contract_address));
+    let (after_execute_msgs, after_execute_events) =
+        plugin_hook_msgs(deps, plugins, &mut verified_plugins, |msgs| {
+            PyxisPluginExecuteMsg::AfterExecute {
+                msgs,
+                call_info: call_info.clone(),
+                is_authz: false,
+                tx_id,
+                balance_changes: vec![],
+            }
+        })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "execute_from_plugin")
+        .add_attribute("plugin_address", info.sender)
+        .add_attribute("tx_id", tx_id.to_string())
+        .add_submessages(pre_execute_msgs)
+        .add_messages(msgs)
+        .add_submessages(after_execute_msgs)
+        .add_events(pre_execute_events)
+        .add_events(after_execute_events))
+}
+
+/// check that the messages executed by a plugin stay in its scope
+/// and encode them as the sdk messages passed to the hooks
+fn scoped_sdk_msgs(
+    deps: Deps,
+    env: &Env,
+    plugin_address: &Addr,
+    scope: &PluginScope,
+    msgs: &[CosmosMsg],
+) -> Result<Vec<SdkMsg>, ContractError> {
+    let out_of_scope = || ContractError::PluginScopeExceeded {
+        plugin_address: plugin_address.to_string(),
+    };
+    let to_proto_coins = |coins: &[Coin]| -> Vec<ProtoCoin> {
+        coins
+            .iter()
+            .map(|coin| ProtoCoin {
+                denom: coin.denom.clone(),
+                amount: coin.amount.to_string(),
+            })
+            .collect()
+    };
+
+    let mut sent: Vec<Coin> = Vec::new();
+    let mut sdk_msgs: Vec<SdkMsg> = Vec::new();
+    for msg in msgs {
+        let (recipient, amount, sdk_msg) = match msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount })
+                if scope.msg_types.contains(&ScopedMsgType::BankSend) =>
+            {
+                let sdk_msg = SdkMsg {
+                    type_url: format!("/{}", MsgSend::full_name()),
+                    value: Binary::from(
+                        MsgSend {
+                            from_address: env.contract.address.to_string(),
+                            to_address: to_address.clone(),
+                            amount: to_proto_coins(amount),
+                        }
+                        .encode_to_vec(),
+                    ),
+                };
+                (to_address, amount, sdk_msg)
+            }
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) if scope.msg_types.contains(&ScopedMsgType::WasmExecute) => {
+                // plugins can not change this smart account or call the other plugins
+                if *contract_addr == env.contract.address.as_str()
+                    || PLUGINS.has(deps.storage, &Addr::unchecked(contract_addr))
+                {
+                    return Err(out_of_scope());
+                }
+                let sdk_msg = SdkMsg {
+                    type_url: format!("/{}", MsgExecuteContract::full_name()),
+                    value: Binary::from(
+                        MsgExecuteContract {
+                            sender: env.contract.address.to_string(),
+                            contract: contract_addr.clone(),
+                            msg: msg.to_vec(),
+                            funds: to_proto_coins(funds),
+                        }
+                        .encode_to_vec(),
+                    ),
+                };
+                (contract_addr, funds, sdk_msg)
+            }
+            _ => return Err(out_of_scope()),
+        };
+
+        if !scope.recipients.is_empty()
+            && !scope
+                .recipients
+                .iter()
+                .any(|address| address.as_str() == recipient)
+        {
+            return Err(out_of_scope());
+        }
+
+        for coin in amount {
+            match sent
+                .iter_mut()
+                .find(|sent_coin| sent_coin.denom == coin.denom)
+            {
+                Some(sent_coin) => {
+                    sent_coin.amount = sent_coin
+                        .amount
+                        .checked_add(coin.amount)
+                        .map_err(StdError::from)?;
+                }
+                None => sent.push(coin.clone()),
+            }
+        }
+        sdk_msgs.push(sdk_msg);
+    }
+
+    // the total amount of each denom must stay under the caps of the scope
+    for coin in &sent {
+        let max_amount = scope
+            .max_amounts
+            .iter()
+            .find(|max_amount| max_amount.denom == coin.denom);
+        if max_amount.map_or(true, |max_amount| coin.amount > max_amount.amount) {
+            return Err(out_of_scope());
+        }
+    }
+
+    Ok(sdk_msgs)
+}
+
+/// return the version of the plugin interface supported by a plugin
+/// plugins which do not answer the `InterfaceInfo` query implement the first version
+fn plugin_interface_version(
+    querier: &QuerierWrapper,
+    plugin_address: &Addr,
+    plugin_type: &PluginType,
+) -> Result<u32, ContractError> {
+    let Ok(interface_info) = querier.query_wasm_smart::<InterfaceInfoResponse>(
+        plugin_address,
+        &PyxisPluginQueryMsg::InterfaceInfo {},
+    ) else {
+        return Ok(LEGACY_PLUGIN_INTERFACE_VERSION);
+    };
+
+    if interface_info.version < MIN_PLUGIN_INTERFACE_VERSION
+        || interface_info.version > PLUGIN_INTERFACE_VERSION
+    {
+        return Err(ContractError::IncompatiblePluginInterface {
+            version: interface_info.version,
+            min: MIN_PLUGIN_INTERFACE_VERSION,
+            max: PLUGIN_INTERFACE_VERSION,
+        });
+    }
+
+    // the plugin must implement the hooks called for its type
+    let required_hooks = match plugin_type {
+        PluginType::Recovery => vec![PluginHook::Recover],
+        PluginType::Other => vec![PluginHook::PreExecute, PluginHook::AfterExecute],
+    };
+    for hook in required_hooks {
+        if !interface_info.hooks.contains(&hook) {
+            return Err(ContractError::PluginHookNotImplemented {
+                hook: format!("{:?}", hook),
+            });
+        }
+    }
+
+    Ok(interface_info.version)
 }
 
 /// Unregister a plugin from this smart account
 /// Only this smart account can unregister a plugin of itself
-pub fn unregister_plugin(
+fn unregister_plugin(
     deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
+    _env: &Env,
+    plugin_infos: &mut PluginInfos,
     plugin_address: Addr,
+    funds: Vec<Coin>,
 ) -> Result<Response, ContractError> {
-    let plugin = PLUGINS.load(deps.storage, &plugin_address)?;
+    let plugin = PLUGINS
+        .may_load(deps.storage, &plugin_address)?
+        .ok_or(ContractError::PluginNotRegistered {})?;
+
+    PLUGINS.remove(deps.storage, &plugin_address);
 
-    // if the plugin is a recovery plugin, disable recoverable
+    // if the plugin is a recovery plugin, make sure the recovery threshold is still reachable
     if plugin.plugin_type == PluginType::Recovery {
-        let mut config = CONFIG.load(deps.storage)?;
-        if config.recoverable {
-            config.recoverable = false;
-            CONFIG.save(deps.storage, &config)?;
-        }
+        sync_recovery_policy(deps.storage)?;
     }
 
-    PLUGINS.remove(deps.storage, &plugin_address);
+    let notify_msg = notify_plugin_manager(
+        &CONFIG.load(deps.storage)?.plugin_manager_addr,
+        &PMExecuteMsg::PluginUnregistered {
+            plugin_address: plugin_address.to_string(),
+        },
+    )?;
 
     // call plugin manager to check if this plugin is enabled
-    let plugin_manager_addr = CONFIG.load(deps.storage)?.plugin_manager_addr;
-    let query_plugin_msg = PMQueryMsg::PluginInfo {
-        address: plugin_address.to_string(),
-    };
-    let plugin_info: Result<PluginResponse, StdError> =
-        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: plugin_manager_addr.to_string(),
-            msg: to_json_binary(&query_plugin_msg)?,
-        }));
+    let plugin_info = plugin_infos.get(&deps.querier, &plugin_address);
 
     // if query error or plugin is diabled, just return
     // else call unregister message
-    if plugin_info.is_err() || !plugin_info.unwrap().enabled {
-        return Ok(Response::new().add_attribute("action", "unregister_plugin"));
+    if !plugin_info.map_or(false, |plugin_info| plugin_info.enabled) {
+        return Ok(Response::new()
+            .add_attribute("action", "unregister_plugin")
+            .add_submessage(notify_msg));
     } else {
         // call unregister in the plugin contract
         let unregister_msg = CosmosMsg::Wasm(wasm_execute(
             plugin_address.as_str(),
             &PyxisPluginExecuteMsg::Unregister {},
-            info.funds,
+            funds,
         )?);
 
         return Ok(Response::new()
             .add_attribute("action", "unregister_plugin")
-            .add_message(unregister_msg));
+            .add_message(unregister_msg)
+            .add_submessage(notify_msg));
     }
 }
 
 fn update_plugin(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    _env: &Env,
+    plugin_infos: &mut PluginInfos,
     plugin_address: Addr,
     status: PluginStatus,
 ) -> Result<Response, ContractError> {
-    let mut plugin = PLUGINS.load(deps.storage, &plugin_address)?;
+    let mut plugin = PLUGINS
+        .may_load(deps.storage, &plugin_address)?
+        .ok_or(ContractError::PluginNotRegistered {})?;
 
-    assert!(plugin.status != status, "Plugin status not change");
+    if plugin.status == status {
+        return Err(ContractError::PluginStatusUnchanged {});
+    }
+
+    // only this smart account suspends a migrated plugin
+    // and a suspended plugin must be registered again to be used
+    if status == PluginStatus::Suspended || plugin.status == PluginStatus::Suspended {
+        return Err(ContractError::PluginSuspended {});
+    }
 
     match status {
         PluginStatus::Inactive => {
             // call plugin manager to check if this plugin is enabled
-            let plugin_manager_addr = CONFIG.load(deps.storage)?.plugin_manager_addr;
-            let query_plugin_msg = PMQueryMsg::PluginInfo {
-                address: plugin_address.to_string(),
-            };
-            let plugin_info: Result<PluginResponse, StdError> =
-                deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-                    contract_addr: plugin_manager_addr.to_string(),
-                    msg: to_json_binary(&query_plugin_msg)?,
-                }));
-
-            if plugin_info.is_ok() && plugin_info.unwrap().enabled {
-                return Err(ContractError::Std(StdError::generic_err(
-                    "Plugin is enabled, cannot deactivate",
-                )));
+            let plugin_info = plugin_infos.get(&deps.querier, &plugin_address);
+
+            if plugin_info.map_or(false, |plugin_info| plugin_info.enabled) {
+                return Err(ContractError::PluginEnabled {});
             }
         }
         _ => {}
@@ -532,13 +1817,686 @@ fn update_plugin(
     plugin.status = status;
     PLUGINS.save(deps.storage, &plugin_address, &plugin)?;
 
+    if plugin.plugin_type == PluginType::Recovery {
+        sync_recovery_policy(deps.storage)?;
+    }
+
     Ok(Response::new().add_attribute("action", "update_plugin"))
 }
 
+/// Update the config of a plugin of this smart account
+/// Only this smart account can update the config of its plugins
+/// the new config is forwarded to the plugin which can reject it
+fn update_plugin_config(
+    deps: DepsMut,
+    _env: &Env,
+    plugin_address: Addr,
+    config: String,
+    funds: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let mut plugin = PLUGINS
+        .may_load(deps.storage, &plugin_address)?
+        .ok_or(ContractError::PluginNotRegistered {})?;
+    if plugin.status != PluginStatus::Active {
+        return Err(ContractError::PluginNotActive {});
+    }
+
+    // the new config may track other denoms
+    plugin.config = config.clone();
+    plugin.tracked_denoms = None;
+    PLUGINS.save(deps.storage, &plugin_address, &plugin)?;
+
+    let update_config_msg = CosmosMsg::Wasm(wasm_execute(
+        plugin_address.as_str(),
+        &PyxisPluginExecuteMsg::UpdateConfig { config },
+        funds,
+    )?);
+
+    Ok(Response::new()
+        .add_attribute("action", "update_plugin_config")
+        .add_attribute("plugin_address", plugin_address)
+        .add_message(update_config_msg))
+}
+
+/// Update the number of recovery plugins that must approve a recovery
+/// Only this smart account can update its recovery policy
+/// the update waits for the timelock of this smart account
+fn update_recovery_policy(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    threshold: u32,
+) -> Result<Response, ContractError> {
+    let timelock = CONFIG.load(deps.storage)?.timelock;
+    if timelock > 0 {
+        return queue_action(
+            deps,
+            &env,
+            timelock,
+            SensitiveAction::UpdateRecoveryPolicy { threshold },
+        );
+    }
+
+    set_recovery_policy(deps, threshold)
+}
+
+fn set_recovery_policy(deps: DepsMut, threshold: u32) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.recoverable.is_none() {
+        return Err(ContractError::RecoveryNotEnabled {});
+    }
+
+    let recovery_plugins = active_recovery_plugins(deps.storage)?;
+    if threshold == 0 || threshold as usize > recovery_plugins.len() {
+        return Err(ContractError::InvalidRecoveryThreshold {
+            threshold,
+            recovery_plugins: recovery_plugins.len() as u32,
+        });
+    }
+
+    config.recoverable = Some(RecoveryPolicy { threshold });
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_recovery_policy")
+        .add_attribute("threshold", threshold.to_string()))
+}
+
+/// Update the config of this smart account
+/// Only this smart account can update its config
+fn update_config(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    plugin_manager_addr: Option<Addr>,
+    plugin_gas_budget: Option<u64>,
+    session_keys_only: Option<bool>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut response = Response::new().add_attribute("action", "update_config");
+
+    if let Some(session_keys_only) = session_keys_only {
+        config.session_keys_only = session_keys_only;
+        response = response.add_attribute("session_keys_only", session_keys_only.to_string());
+    }
+
+    if let Some(plugin_gas_budget) = plugin_gas_budget {
+        config.plugin_gas_budget = plugin_gas_budget;
+        response = response.add_attribute("plugin_gas_budget", plugin_gas_budget.to_string());
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    // changing the plugin manager waits for the timelock of this smart account
+    if let Some(plugin_manager_addr) = plugin_manager_addr {
+        if plugin_manager_addr != config.plugin_manager_addr {
+            let manager_response = if config.timelock > 0 {
+                let plugin_manager_addr =
+                    verify_plugin_manager(deps.as_ref(), &plugin_manager_addr)?;
+                queue_action(
+                    deps,
+                    &env,
+                    config.timelock,
+                    SensitiveAction::UpdatePluginManager {
+                        plugin_manager_addr,
+                    },
+                )?
+            } else {
+                set_plugin_manager(deps, plugin_manager_addr)?
+            };
+            response = response
+                .add_submessages(manager_response.messages)
+                .add_attributes(manager_response.attributes)
+                .add_events(manager_response.events);
+        }
+    }
+
+    Ok(response)
+}
+
+/// check that the new plugin manager answers the plugin manager queries
+fn verify_plugin_manager(deps: Deps, plugin_manager_addr: &Addr) -> Result<Addr, ContractError> {
+    let plugin_manager_addr = deps.api.addr_validate(plugin_manager_addr.as_str())?;
+
+    let _: PMAllPluginsResponse = deps
+        .querier
+        .query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: plugin_manager_addr.to_string(),
+            msg: to_json_binary(&PMQueryMsg::AllPlugins {
+                start_after: None,
+                limit: Some(1),
+            })?,
+        }))
+        .map_err(|_| ContractError::InvalidPluginManager {
+            address: plugin_manager_addr.to_string(),
+        })?;
+
+    Ok(plugin_manager_addr)
+}
+
+/// change the plugin manager of this smart account
+/// registered plugins are checked against the new plugin manager,
+/// the previous plugin manager is notified that they are unregistered
+/// and the new one that the plugins which are not suspended are registered
+fn set_plugin_manager(
+    mut deps: DepsMut,
+    plugin_manager_addr: Addr,
+) -> Result<Response, ContractError> {
+    let plugin_manager_addr = verify_plugin_manager(deps.as_ref(), &plugin_manager_addr)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let previous_plugin_manager_addr = config.plugin_manager_addr;
+    config.plugin_manager_addr = plugin_manager_addr.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    let suspend_events = check_plugins(deps.branch())?;
+
+    let plugins = PLUGINS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut notify_msgs: Vec<SubMsg> = Vec::new();
+    for (address, _) in &plugins {
+        notify_msgs.push(notify_plugin_manager(
+            &previous_plugin_manager_addr,
+            &PMExecuteMsg::PluginUnregistered {
+                plugin_address: address.to_string(),
+            },
+        )?);
+    }
+    for (address, plugin) in &plugins {
+        if plugin.status != PluginStatus::Suspended {
+            notify_msgs.push(notify_plugin_manager(
+                &plugin_manager_addr,
+                &PMExecuteMsg::PluginRegistered {
+                    plugin_address: address.to_string(),
+                },
+            )?);
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("plugin_manager_addr", plugin_manager_addr)
+        .add_submessages(notify_msgs)
+        .add_events(suspend_events))
+}
+
+/// Update the timelock of this smart account
+/// Only this smart account can update its timelock
+/// lowering the timelock is queued so that a stolen key can not remove it immediately
+fn update_timelock(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    timelock: u64,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if timelock < config.timelock {
+        return queue_action(
+            deps,
+            &env,
+            config.timelock,
+            SensitiveAction::UpdateTimelock { timelock },
+        );
+    }
+
+    config.timelock = timelock;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_timelock")
+        .add_attribute("timelock", timelock.to_string()))
+}
+
+/// queue a sensitive action which can be executed once the timelock has passed
+fn queue_action(
+    deps: DepsMut,
+    env: &Env,
+    timelock: u64,
+    action: SensitiveAction,
+) -> Result<Response, ContractError> {
+    // plugin actions can only be queued for registered plugins
+    match &action {
+        SensitiveAction::UnregisterPlugin { plugin_address }
+        | SensitiveAction::DeactivatePlugin { plugin_address }
+        | SensitiveAction::UpdatePluginConfig { plugin_address, .. } => {
+            if !PLUGINS.has(deps.storage, plugin_address) {
+                return Err(ContractError::PluginNotRegistered {});
+            }
+        }
+        SensitiveAction::UpdatePluginManager { .. }
+        | SensitiveAction::UpdateTimelock { .. }
+        | SensitiveAction::UpdateGuardians { .. }
+        | SensitiveAction::UpdateRecoveryPolicy { .. }
+        | SensitiveAction::Unfreeze {} => {}
+    }
+
+    let id = NEXT_ACTION_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_ACTION_ID.save(deps.storage, &(id + 1))?;
+
+    let execute_after = env.block.time.seconds() + timelock;
+    PENDING_ACTIONS.save(
+        deps.storage,
+        id,
+        &PendingAction {
+            id,
+            action,
+            execute_after: Uint64::from(execute_after),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "queue_pending_action")
+        .add_attribute("pending_action_id", id.to_string())
+        .add_attribute("execute_after", execute_after.to_string()))
+}
+
+/// Execute a pending action of this smart account once its timelock has passed
+/// Only this smart account can execute its pending actions
+fn execute_pending_action(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let pending_action = PENDING_ACTIONS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::PendingActionNotFound { id })?;
+    if env.block.time.seconds() < pending_action.execute_after.u64() {
+        return Err(ContractError::PendingActionLocked {
+            id,
+            execute_after: pending_action.execute_after.u64(),
+        });
+    }
+
+    PENDING_ACTIONS.remove(deps.storage, id);
+
+    let response = match pending_action.action {
+        SensitiveAction::UnregisterPlugin { plugin_address } => {
+            let mut plugin_infos = PluginInfos::new(deps.storage)?;
+            unregister_plugin(deps, &env, &mut plugin_infos, plugin_address, vec![])?
+        }
+        SensitiveAction::DeactivatePlugin { plugin_address } => {
+            let mut plugin_infos = PluginInfos::new(deps.storage)?;
+            update_plugin(
+                deps,
+                &env,
+                &mut plugin_infos,
+                plugin_address,
+                PluginStatus::Inactive,
+            )?
+        }
+        SensitiveAction::UpdatePluginManager {
+            plugin_manager_addr,
+        } => set_plugin_manager(deps, plugin_manager_addr)?,
+        SensitiveAction::UpdateTimelock { timelock } => {
+            let mut config = CONFIG.load(deps.storage)?;
+            config.timelock = timelock;
+            CONFIG.save(deps.storage, &config)?;
+            Response::new().add_attribute("timelock", timelock.to_string())
+        }
+        SensitiveAction::UpdatePluginConfig {
+            plugin_address,
+            config,
+        } => update_plugin_config(deps, &env, plugin_address, config, vec![])?,
+        SensitiveAction::UpdateGuardians { guardians } => set_guardians(deps, guardians)?,
+        SensitiveAction::UpdateRecoveryPolicy { threshold } => {
+            set_recovery_policy(deps, threshold)?
+        }
+        SensitiveAction::Unfreeze {} => unfreeze_account(deps)?,
+    };
+
+    Ok(response
+        .add_attribute("action", "execute_pending_action")
+        .add_attribute("pending_action_id", id.to_string()))
+}
+
+/// Cancel a pending action of this smart account
+/// this smart account, its guardians and its active recovery plugins can cancel pending actions
+fn cancel_pending_action(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    if !PENDING_ACTIONS.has(deps.storage, id) {
+        return Err(ContractError::PendingActionNotFound { id });
+    }
+
+    PENDING_ACTIONS.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_pending_action")
+        .add_attribute("pending_action_id", id.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+/// Register a session key to this smart account
+/// Only this smart account can register a session key for itself
+fn register_session_key(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    session_key: SessionKey,
+) -> Result<Response, ContractError> {
+    deps.api.addr_validate(session_key.address.as_str())?;
+    for contract in &session_key.contracts {
+        deps.api.addr_validate(contract.as_str())?;
+    }
+
+    if session_key.expiration.u64() <= env.block.time.seconds() {
+        return Err(ContractError::InvalidSessionKeyExpiration {});
+    }
+
+    SESSION_KEYS.save(deps.storage, &session_key.address, &session_key)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_session_key")
+        .add_attribute("address", session_key.address))
+}
+
+/// Revoke a session key of this smart account
+/// Only this smart account can revoke its session keys
+fn revoke_session_key(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    address: Addr,
+) -> Result<Response, ContractError> {
+    if !SESSION_KEYS.has(deps.storage, &address) {
+        return Err(ContractError::SessionKeyNotFound {
+            address: address.to_string(),
+        });
+    }
+
+    SESSION_KEYS.remove(deps.storage, &address);
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_session_key")
+        .add_attribute("address", address))
+}
+
+/// Revoke all the session keys of this smart account
+/// Only this smart account can revoke its session keys
+fn revoke_all_session_keys(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let addresses = SESSION_KEYS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for address in &addresses {
+        SESSION_KEYS.remove(deps.storage, address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_all_session_keys")
+        .add_attribute("revoked", addresses.len().to_string()))
+}
+
+/// Replace the guardians of this smart account
+/// Only this smart account can update its guardians
+/// the update waits for the timelock of this smart account
+fn update_guardians(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    guardians: Vec<Addr>,
+) -> Result<Response, ContractError> {
+    let guardians = guardians
+        .iter()
+        .map(|guardian| deps.api.addr_validate(guardian.as_str()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let timelock = CONFIG.load(deps.storage)?.timelock;
+    if timelock > 0 {
+        return queue_action(
+            deps,
+            &env,
+            timelock,
+            SensitiveAction::UpdateGuardians { guardians },
+        );
+    }
+
+    set_guardians(deps, guardians)
+}
+
+fn set_guardians(deps: DepsMut, guardians: Vec<Addr>) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    config.guardians = guardians;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_guardians")
+        .add_attribute("guardians", config.guardians.len().to_string()))
+}
+
+/// Freeze this smart account
+/// this smart account or one of its guardians can freeze it,
+/// every transaction is then rejected in `after_execute` except `Unfreeze`
+fn freeze(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.frozen {
+        return Err(ContractError::AccountFrozen {});
+    }
+
+    config.frozen = true;
+    CONFIG.save(deps.storage, &config)?;
+    UNFREEZE_APPROVALS.remove(deps.storage);
+
+    // an unfreeze queued before must not lift this freeze
+    let pending_actions = PENDING_ACTIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, pending_action) in pending_actions {
+        if matches!(pending_action.action, SensitiveAction::Unfreeze {}) {
+            PENDING_ACTIONS.remove(deps.storage, id);
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "freeze")
+        .add_attribute("sender", info.sender))
+}
+
+/// Unfreeze this smart account
+/// a smart account with guardians is unfrozen once a majority of its guardians approve it,
+/// a smart account without guardians can unfreeze itself
+/// with a timelock, unfreezing itself is queued and can be cancelled by the guardians
+/// a successful recovery also unfreezes this smart account
+fn unfreeze(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.frozen {
+        return Err(ContractError::AccountNotFrozen {});
+    }
+
+    if info.sender == env.contract.address {
+        if config.timelock > 0 {
+            return queue_action(deps, &env, config.timelock, SensitiveAction::Unfreeze {});
+        }
+        // a stolen key must not be able to lift the freeze of the guardians
+        if !config.guardians.is_empty() {
+            return Err(ContractError::UnfreezeNotAllowed {});
+        }
+    } else {
+        let mut approvals = UNFREEZE_APPROVALS
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        approvals.retain(|guardian| config.guardians.contains(guardian));
+        if !approvals.contains(&info.sender) {
+            approvals.push(info.sender.clone());
+        }
+
+        let quorum = config.guardians.len() / 2 + 1;
+        if approvals.len() < quorum {
+            UNFREEZE_APPROVALS.save(deps.storage, &approvals)?;
+            return Ok(Response::new()
+                .add_attribute("action", "approve_unfreeze")
+                .add_attribute("guardian", info.sender)
+                .add_attribute("approvals", approvals.len().to_string())
+                .add_attribute("quorum", quorum.to_string()));
+        }
+    }
+
+    Ok(unfreeze_account(deps)?.add_attribute("sender", info.sender))
+}
+
+fn unfreeze_account(deps: DepsMut) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if !config.frozen {
+        return Err(ContractError::AccountNotFrozen {});
+    }
+
+    config.frozen = false;
+    CONFIG.save(deps.storage, &config)?;
+    UNFREEZE_APPROVALS.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "unfreeze"))
+}
+
+/// load all active recovery plugins of this smart account
+fn active_recovery_plugins(storage: &dyn Storage) -> StdResult<Vec<Plugin>> {
+    let plugins = PLUGINS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(plugins
+        .into_iter()
+        .map(|(_, plugin)| plugin)
+        .filter(|plugin| {
+            plugin.status == PluginStatus::Active && plugin.plugin_type == PluginType::Recovery
+        })
+        .collect())
+}
+
+/// keep the recovery policy consistent with the active recovery plugins
+/// recovery is enabled with a threshold of one when there is a recovery plugin,
+/// the threshold is lowered when it can not be reached anymore
+/// and recovery is disabled when there is no recovery plugin left
+fn sync_recovery_policy(storage: &mut dyn Storage) -> StdResult<()> {
+    let mut config = CONFIG.load(storage)?;
+    let recovery_plugins = active_recovery_plugins(storage)?.len() as u32;
+
+    config.recoverable = match config.recoverable {
+        _ if recovery_plugins == 0 => None,
+        None => Some(RecoveryPolicy { threshold: 1 }),
+        Some(policy) => Some(RecoveryPolicy {
+            threshold: policy.threshold.min(recovery_plugins),
+        }),
+    };
+
+    CONFIG.save(storage, &config)
+}
+
 /// Handling contract query
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
+        QueryMsg::Config {} => {
+            let config = CONFIG.load(deps.storage)?;
+            to_json_binary(&ConfigResponse {
+                plugin_manager_addr: config.plugin_manager_addr,
+                plugin_gas_budget: config.plugin_gas_budget,
+                recoverable: config.recoverable,
+                session_keys_only: config.session_keys_only,
+                guardians: config.guardians,
+                frozen: config.frozen,
+                timelock: config.timelock,
+            })
+        }
+        QueryMsg::Plugin { address } => {
+            let plugin = PLUGINS.may_load(deps.storage, &Addr::unchecked(address))?;
+            to_json_binary(&PluginResponse { plugin })
+        }
+        QueryMsg::PluginsByType {
+            plugin_type,
+            start_after,
+            limit,
+        } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+
+            let plugins = PLUGINS
+                .range(deps.storage, start, None, Order::Ascending)
+                .filter(|item| match item {
+                    Ok((_, plugin)) => plugin.plugin_type == plugin_type,
+                    Err(_) => true,
+                })
+                .take(limit)
+                .map(|item| item.map(|(_, plugin)| plugin))
+                .collect::<StdResult<_>>()?;
+
+            to_json_binary(&PluginsByTypeResponse { plugins })
+        }
+        QueryMsg::RecoveryStatus {} => {
+            let config = CONFIG.load(deps.storage)?;
+            let recovery_plugins = active_recovery_plugins(deps.storage)?;
+            let legacy_recovery_plugins = recovery_plugins
+                .iter()
+                .filter(|plugin| plugin.interface_version < RECOVERY_NONCE_INTERFACE_VERSION)
+                .map(|plugin| plugin.contract_address.clone())
+                .collect();
+
+            to_json_binary(&RecoveryStatusResponse {
+                recoverable: config.recoverable.is_some(),
+                threshold: config.recoverable.map(|policy| policy.threshold),
+                recovery_plugins: recovery_plugins
+                    .into_iter()
+                    .map(|plugin| plugin.contract_address)
+                    .collect(),
+                legacy_recovery_plugins,
+                nonce: RECOVERY_NONCE.may_load(deps.storage)?.unwrap_or_default(),
+            })
+        }
+        QueryMsg::RecoveryLog { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(Bound::exclusive);
+
+            let records = RECOVERY_LOG
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, record)| record))
+                .collect::<StdResult<_>>()?;
+
+            to_json_binary(&RecoveryLogResponse { records })
+        }
+        QueryMsg::SessionKeys { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+
+            let session_keys = SESSION_KEYS
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, session_key)| session_key))
+                .collect::<StdResult<_>>()?;
+
+            to_json_binary(&SessionKeysResponse { session_keys })
+        }
+        QueryMsg::PendingActions { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(Bound::exclusive);
+
+            let actions = PENDING_ACTIONS
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, action)| action))
+                .collect::<StdResult<_>>()?;
+
+            to_json_binary(&PendingActionsResponse { actions })
+        }
+        QueryMsg::SimulateExecute {
+            msgs,
+            call_info,
+            is_authz,
+        } => to_json_binary(&simulate_execute(deps, env, msgs, call_info, is_authz)),
+        QueryMsg::ContractVersion {} => {
+            let version = get_contract_version(deps.storage)?;
+            to_json_binary(&ContractVersionResponse {
+                contract: version.contract,
+                version: version.version,
+            })
+        }
         QueryMsg::AllPlugins { start_after, limit } => {
             let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
             let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
@@ -554,11 +2512,90 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     }
 }
 
+/// check a transaction with the rules of `after_execute` without changing the state
+/// every plugin called in `after_execute` gives its verdict with `ValidateExecute`
+fn simulate_execute(
+    deps: Deps,
+    env: Env,
+    msgs: Vec<SdkMsg>,
+    call_info: CallInfo,
+    is_authz: bool,
+) -> SimulateExecuteResponse {
+    // the spending of the session key is checked without being recorded
+    let plugins = check_tx(deps, &env, &msgs, is_authz)
+        .and_then(|disable_plugins| {
+            if is_authz {
+                validate_session_key(deps.storage, &env, &msgs, &call_info)?;
+            }
+            Ok(disable_plugins)
+        })
+        .and_then(|disable_plugins| hook_plugins(deps, &msgs, is_authz, &disable_plugins))
+        .and_then(|plugins| {
+            // plugins migrated to another code would be suspended instead of being called
+            let mut running_plugins = Vec::new();
+            for (plugin, plugin_msgs) in plugins {
+                if running_code(deps, &plugin)?.1 {
+                    running_plugins.push((plugin, plugin_msgs));
+                }
+            }
+            Ok(running_plugins)
+        });
+    let plugins = match plugins {
+        Ok(plugins) => plugins,
+        Err(err) => {
+            return SimulateExecuteResponse {
+                valid: false,
+                reason: Some(err.to_string()),
+                plugins: vec![],
+            }
+        }
+    };
+
+    let verdicts: Vec<PluginVerdict> = plugins
+        .into_iter()
+        .map(|(plugin, plugin_msgs)| {
+            let verdict: StdResult<ValidateExecuteResponse> = deps.querier.query_wasm_smart(
+                &plugin.contract_address,
+                &PyxisPluginQueryMsg::ValidateExecute {
+                    address: env.contract.address.to_string(),
+                    msgs: plugin_msgs,
+                    call_info: call_info.clone(),
+                    is_authz,
+                },
+            );
+            match verdict {
+                Ok(verdict) => PluginVerdict {
+                    plugin_address: plugin.contract_address,
+                    valid: verdict.valid,
+                    reason: verdict.reason,
+                },
+                // a plugin which can not validate the transaction is counted as a rejection
+                Err(err) => PluginVerdict {
+                    plugin_address: plugin.contract_address,
+                    valid: false,
+                    reason: Some(err.to_string()),
+                },
+            }
+        })
+        .collect();
+
+    SimulateExecuteResponse {
+        valid: verdicts.iter().all(|verdict| verdict.valid),
+        reason: None,
+        plugins: verdicts,
+    }
+}
+
 /// Handling submessage reply.
 /// For more info on submessage and reply, see https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#submessages
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, _msg: Reply) -> Result<Response, ContractError> {
-    // With `Response` type, it is still possible to dispatch message to invoke external logic.
-    // See: https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#dispatching-messages
-    Ok(Response::new())
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        RECOVER_REPLY_ID => handle_recover_reply(deps, env, msg),
+        // a failed notification of the plugin manager is ignored
+        NOTIFY_PLUGIN_MANAGER_REPLY_ID => {
+            Ok(Response::new().add_attribute("action", "notify_plugin_manager_failed"))
+        }
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
 }
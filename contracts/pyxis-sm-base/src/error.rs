@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Instantiate2AddressError, StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,9 +6,147 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("Plugin instance address can not be derived: {0}")]
+    Instantiate2Address(Instantiate2AddressError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Authz transactions can not execute messages of the smart account")]
+    AuthzNotAllowed {},
+
+    #[error("Smart account is frozen")]
+    AccountFrozen {},
+
+    #[error("Smart account is not frozen")]
+    AccountNotFrozen {},
+
+    #[error("Smart account with guardians and no timelock must be unfrozen by its guardians or a recovery")]
+    UnfreezeNotAllowed {},
+
+    #[error("Not allowed action")]
+    NotAllowedAction {},
+
+    #[error("Malformed message: {type_url}")]
+    MalformedMessage { type_url: String },
+
+    #[error("Too many nested messages, maximum depth is {max_depth}")]
+    TooManyNestedMessages { max_depth: u32 },
+
+    #[error("Plugin is already registered")]
+    PluginAlreadyRegistered {},
+
+    #[error("Plugin is not registered")]
+    PluginNotRegistered {},
+
+    #[error("Plugin is not allowed by the plugin manager")]
+    PluginNotAllowed {},
+
+    #[error("Plugin is disabled")]
+    PluginDisabled {},
+
+    #[error("Plugin is enabled, cannot deactivate")]
+    PluginEnabled {},
+
+    #[error("Plugin is not active")]
+    PluginNotActive {},
+
+    #[error("Plugin is suspended, it must be registered again")]
+    PluginSuspended {},
+
+    #[error("Plugin status not change")]
+    PluginStatusUnchanged {},
+
+    #[error("Invalid plugin code_id: expected {expected}, actual {actual}")]
+    CodeIdMismatch { expected: u64, actual: u64 },
+
+    #[error("Plugin max gas is not declared")]
+    PluginMaxGasNotDeclared {},
+
+    #[error("Incompatible plugin interface version {version}: supported {min} to {max}")]
+    IncompatiblePluginInterface { version: u32, min: u32, max: u32 },
+
+    #[error("Plugin does not implement the {hook} hook")]
+    PluginHookNotImplemented { hook: String },
+
+    #[error("Message is out of the scope of plugin {plugin_address}")]
+    PluginScopeExceeded { plugin_address: String },
+
+    #[error("Plugin gas budget exceeded: {required}/{budget}")]
+    PluginGasBudgetExceeded { required: u64, budget: u64 },
+
+    #[error("No plugin operation")]
+    EmptyPluginOps {},
+
+    #[error("Funds are not accepted with plugin operations batch")]
+    BatchFundsNotAccepted {},
+
+    #[error("Invalid plugin manager: {address}")]
+    InvalidPluginManager { address: String },
+
+    #[error(
+        "Recovery plugin with interface version {version} does not receive the recovery nonce"
+    )]
+    LegacyRecoveryPlugin { version: u32 },
+
+    #[error("Recovery is not enabled")]
+    RecoveryNotEnabled {},
+
+    #[error("Invalid recovery threshold {threshold} for {recovery_plugins} recovery plugins")]
+    InvalidRecoveryThreshold {
+        threshold: u32,
+        recovery_plugins: u32,
+    },
+
+    #[error("Recovery threshold not reached: {approvals}/{threshold}")]
+    RecoveryThresholdNotReached { approvals: u32, threshold: u32 },
+
+    #[error("No recovery plugin is pending")]
+    NoPendingRecovery {},
+
+    #[error("Not a session key: {address}")]
+    NotSessionKey { address: String },
+
+    #[error("Session key not found: {address}")]
+    SessionKeyNotFound { address: String },
+
+    #[error("Session key is expired: {address}")]
+    SessionKeyExpired { address: String },
+
+    #[error("Session key expiration must be in the future")]
+    InvalidSessionKeyExpiration {},
+
+    #[error("Session key is not allowed to execute {type_url}")]
+    SessionKeyMessageNotAllowed { type_url: String },
+
+    #[error("Session key is not allowed to execute contract {contract}")]
+    SessionKeyContractNotAllowed { contract: String },
+
+    #[error("Session key spend limit exceeded for {denom}: {spent}/{limit}")]
+    SessionKeySpendLimitExceeded {
+        denom: String,
+        limit: Uint128,
+        spent: Uint128,
+    },
+
+    #[error("Pending action not found: {id}")]
+    PendingActionNotFound { id: u64 },
+
+    #[error("Pending action {id} can not be executed before {execute_after}")]
+    PendingActionLocked { id: u64, execute_after: u64 },
+
+    #[error("Invalid version: {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Cannot migrate from contract {contract}")]
+    InvalidMigrationContract { contract: String },
+
+    #[error("Cannot migrate from version {from} to older version {to}")]
+    MigrationDowngrade { from: String, to: String },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
     // Add any other custom errors you like here.
@@ -130,6 +130,8 @@ pub fn setup_smart_account(
 
     let init_msg = serde_json_wasm::to_string(&InstantiateMsg {
         plugin_manager_addr: contracts.get("plugin_manager").unwrap().clone(),
+        plugin_gas_budget: None,
+        initial_plugins: vec![],
     })
     .unwrap()
     .as_bytes()
@@ -255,6 +257,7 @@ pub fn allow_plugin(
                 code_id: *code_ids.get(plugin_name).unwrap(),
                 version: "v0.1.0".to_string(),
                 enabled: true,
+                max_gas: 1_000_000,
             },
         },
         &vec![],
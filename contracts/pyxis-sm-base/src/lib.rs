@@ -1,6 +1,9 @@
+mod authz;
 pub mod contract;
-mod error;
+pub mod error;
+mod migrations;
 pub mod msg;
+mod session_keys;
 pub mod state;
 
 #[cfg(test)]
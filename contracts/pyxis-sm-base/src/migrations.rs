@@ -0,0 +1,239 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    Addr, CodeInfoResponse, ContractInfoResponse, DepsMut, HexBinary, Order, QueryRequest,
+    StdResult, WasmQuery,
+};
+use cw_storage_plus::{Item, Map};
+use pyxis_sm::msg::LEGACY_PLUGIN_INTERFACE_VERSION;
+pub use pyxis_sm::plugin_manager_msg::LEGACY_PLUGIN_MAX_GAS;
+use pyxis_sm::plugin_manager_msg::{
+    PluginResponse as PMPluginResponse, PluginType, QueryMsg as PMQueryMsg,
+};
+
+use crate::error::ContractError;
+use crate::state::{Config, Plugin, PluginStatus, RecoveryPolicy, CONFIG, PLUGINS};
+
+/// parse a "major.minor.patch" version so that versions can be compared
+pub fn parse_version(version: &str) -> Result<(u64, u64, u64), ContractError> {
+    let invalid_version = || ContractError::InvalidVersion {
+        version: version.to_string(),
+    };
+    let parts = version
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid_version())?;
+
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => Err(invalid_version()),
+    }
+}
+
+/// run every state transform needed to migrate from `from_version` to the current version
+/// transforms are applied in order, each one migrating the state of a single version
+pub fn migrate_state(
+    mut deps: DepsMut,
+    from_version: (u64, u64, u64),
+    default_gas_budget: u64,
+) -> StdResult<()> {
+    if from_version < (0, 2, 0) {
+        v0_1::migrate(deps.branch(), default_gas_budget)?;
+    }
+    if from_version < (0, 3, 0) {
+        v0_2::migrate(deps)?;
+    }
+
+    Ok(())
+}
+
+/// state of v0.1.0, migrated to v0.2.0 which adds recovery policy, gas budget,
+/// message filters, max gas and code info of plugins
+mod v0_1 {
+    use super::*;
+
+    #[cw_serde]
+    pub struct LegacyConfig {
+        pub plugin_manager_addr: Addr,
+        pub recoverable: bool,
+    }
+
+    #[cw_serde]
+    pub struct LegacyPlugin {
+        pub name: String,
+        pub plugin_type: PluginType,
+        pub contract_address: Addr,
+        pub status: PluginStatus,
+        pub config: String,
+    }
+
+    pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+    pub const LEGACY_PLUGINS: Map<&Addr, LegacyPlugin> = Map::new("plugins");
+
+    pub fn migrate(deps: DepsMut, default_gas_budget: u64) -> StdResult<()> {
+        let legacy_config = LEGACY_CONFIG.load(deps.storage)?;
+        let legacy_plugins = LEGACY_PLUGINS
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for (address, legacy_plugin) in legacy_plugins {
+            // the code of the plugin is the one allowed by the plugin manager,
+            // a plugin migrated since then is suspended the next time it is called
+            // and a plugin disabled by the plugin manager is suspended now,
+            // a plugin the plugin manager can not answer for keeps its status
+            let plugin_info = deps.querier.query_wasm_smart::<PMPluginResponse>(
+                &legacy_config.plugin_manager_addr,
+                &PMQueryMsg::PluginInfo {
+                    address: address.to_string(),
+                },
+            );
+            let (code_id, status) = match plugin_info {
+                Ok(plugin_info) if plugin_info.enabled => {
+                    (plugin_info.code_id, legacy_plugin.status)
+                }
+                Ok(plugin_info) => (plugin_info.code_id, PluginStatus::Suspended),
+                Err(_) => {
+                    let contract_info: ContractInfoResponse =
+                        deps.querier
+                            .query(&QueryRequest::Wasm(WasmQuery::ContractInfo {
+                                contract_addr: address.to_string(),
+                            }))?;
+                    (contract_info.code_id, legacy_plugin.status)
+                }
+            };
+            let checksum = deps
+                .querier
+                .query::<CodeInfoResponse>(&QueryRequest::Wasm(WasmQuery::CodeInfo { code_id }))
+                .map(|code_info| code_info.checksum)
+                .ok();
+
+            v0_2::LEGACY_PLUGINS.save(
+                deps.storage,
+                &address,
+                &v0_2::LegacyPlugin {
+                    name: legacy_plugin.name,
+                    plugin_type: legacy_plugin.plugin_type,
+                    contract_address: legacy_plugin.contract_address,
+                    status,
+                    config: legacy_plugin.config,
+                    filter: None,
+                    max_gas: LEGACY_PLUGIN_MAX_GAS,
+                    code_id,
+                    checksum,
+                },
+            )?;
+        }
+
+        let plugins = v0_2::LEGACY_PLUGINS
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        // a recoverable account keeps a single recovery plugin approval
+        let has_recovery_plugin = plugins.iter().any(|(_, plugin)| {
+            plugin.plugin_type == PluginType::Recovery && plugin.status == PluginStatus::Active
+        });
+
+        // the legacy gas limit of the migrated plugins is added to the default budget,
+        // so that the account can still register plugins up to the default budget
+        let legacy_plugins_gas = plugins
+            .iter()
+            .filter(|(_, plugin)| plugin.plugin_type == PluginType::Other)
+            .fold(0u64, |total, (_, plugin)| {
+                total.saturating_add(plugin.max_gas)
+            });
+
+        v0_2::LEGACY_CONFIG.save(
+            deps.storage,
+            &v0_2::LegacyConfig {
+                plugin_manager_addr: legacy_config.plugin_manager_addr,
+                plugin_gas_budget: default_gas_budget.saturating_add(legacy_plugins_gas),
+                recoverable: if legacy_config.recoverable && has_recovery_plugin {
+                    Some(RecoveryPolicy { threshold: 1 })
+                } else {
+                    None
+                },
+            },
+        )
+    }
+}
+
+/// state of v0.2.0, migrated to v0.3.0 which adds session keys, guardians, freeze, timelock,
+/// plugin interface versions and plugin scopes
+mod v0_2 {
+    use super::*;
+    use crate::state::MessageFilter;
+
+    #[cw_serde]
+    pub struct LegacyConfig {
+        pub plugin_manager_addr: Addr,
+        pub plugin_gas_budget: u64,
+        pub recoverable: Option<RecoveryPolicy>,
+    }
+
+    #[cw_serde]
+    pub struct LegacyPlugin {
+        pub name: String,
+        pub plugin_type: PluginType,
+        pub contract_address: Addr,
+        pub status: PluginStatus,
+        pub config: String,
+        pub filter: Option<MessageFilter>,
+        pub max_gas: u64,
+        pub code_id: u64,
+        pub checksum: Option<HexBinary>,
+    }
+
+    pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+    pub const LEGACY_PLUGINS: Map<&Addr, LegacyPlugin> = Map::new("plugins");
+
+    pub fn migrate(deps: DepsMut) -> StdResult<()> {
+        let legacy_plugins = LEGACY_PLUGINS
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        // plugins registered before the interface version was negotiated
+        // receive the hook messages of the first version and have no scope
+        for (address, legacy_plugin) in legacy_plugins {
+            PLUGINS.save(
+                deps.storage,
+                &address,
+                &Plugin {
+                    name: legacy_plugin.name,
+                    plugin_type: legacy_plugin.plugin_type,
+                    contract_address: legacy_plugin.contract_address,
+                    status: legacy_plugin.status,
+                    config: legacy_plugin.config,
+                    filter: legacy_plugin.filter,
+                    max_gas: legacy_plugin.max_gas,
+                    code_id: legacy_plugin.code_id,
+                    checksum: legacy_plugin.checksum,
+                    interface_version: LEGACY_PLUGIN_INTERFACE_VERSION,
+                    scope: None,
+                    tracked_denoms: None,
+                },
+            )?;
+        }
+
+        let legacy_config = LEGACY_CONFIG.load(deps.storage)?;
+        CONFIG.save(
+            deps.storage,
+            &Config {
+                plugin_manager_addr: legacy_config.plugin_manager_addr,
+                plugin_gas_budget: legacy_config.plugin_gas_budget,
+                recoverable: legacy_config.recoverable,
+                session_keys_only: false,
+                guardians: vec![],
+                frozen: false,
+                timelock: 0,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+pub use v0_1::{LegacyConfig, LegacyPlugin, LEGACY_CONFIG, LEGACY_PLUGINS};
+#[cfg(test)]
+pub use v0_2::{
+    LegacyConfig as LegacyConfigV0_2, LegacyPlugin as LegacyPluginV0_2,
+    LEGACY_CONFIG as LEGACY_CONFIG_V0_2, LEGACY_PLUGINS as LEGACY_PLUGINS_V0_2,
+};
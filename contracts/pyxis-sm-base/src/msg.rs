@@ -1,11 +1,28 @@
-use crate::state::{Plugin, PluginStatus};
+use crate::state::{
+    MessageFilter, PendingAction, Plugin, PluginScope, PluginStatus, RecoveryPolicy,
+    RecoveryRecord, SessionKey,
+};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg, Uint64};
+use pyxis_sm::msg::{CallInfo, SdkMsg};
+use pyxis_sm::plugin_manager_msg::PluginType;
 
 /// Message type for `instantiate` entry_point
 #[cw_serde]
 pub struct InstantiateMsg {
     pub plugin_manager_addr: Addr,
+    // default to `DEFAULT_PLUGIN_GAS_BUDGET` if not set
+    pub plugin_gas_budget: Option<u64>,
+    // plugins registered at instantiation, checked against the plugin manager as `RegisterPlugin`
+    #[serde(default)]
+    pub initial_plugins: Vec<InitialPlugin>,
+}
+
+/// InitialPlugin is a plugin registered when the smart account is instantiated
+#[cw_serde]
+pub struct InitialPlugin {
+    pub plugin_address: Addr,
+    pub config: String,
 }
 
 /// Message type for `execute` entry_point
@@ -14,6 +31,20 @@ pub enum ExecuteMsg {
     RegisterPlugin {
         plugin_address: Addr,
         config: String,
+        filter: Option<MessageFilter>,
+        // messages the plugin can execute on behalf of this smart account, None for no message
+        scope: Option<PluginScope>,
+    },
+    /// Instantiate a plugin dedicated to this smart account from a template of the plugin manager
+    /// and register it, a smart account has a single instance of each template:
+    /// installing the same template again registers the same instance without instantiating it
+    InstallPlugin {
+        code_id: u64,
+        // instantiate message of the plugin
+        msg: Binary,
+        config: String,
+        filter: Option<MessageFilter>,
+        scope: Option<PluginScope>,
     },
     UnregisterPlugin {
         plugin_address: Addr,
@@ -22,24 +53,279 @@ pub enum ExecuteMsg {
         plugin_address: Addr,
         status: PluginStatus,
     },
+    /// Update the config of an active plugin, the plugin can reject the new config
+    UpdatePluginConfig {
+        plugin_address: Addr,
+        config: String,
+    },
+    /// Register, unregister and update plugins atomically, in order
+    BatchPluginOps {
+        ops: Vec<PluginOp>,
+    },
+    /// Set the number of recovery plugins that must approve a recovery
+    UpdateRecoveryPolicy {
+        threshold: u32,
+    },
+    /// Change the plugin manager and the plugin gas budget of this smart account
+    /// registered plugins unknown to the new plugin manager are suspended
+    UpdateConfig {
+        plugin_manager_addr: Option<Addr>,
+        plugin_gas_budget: Option<u64>,
+        // only allow authz transactions executed by an active session key
+        // when set to true, registering session keys does not turn it on
+        session_keys_only: Option<bool>,
+    },
+    /// Register a session key allowed to execute messages for this smart account through authz
+    /// registering a session key again replaces its scope and resets its spending
+    RegisterSessionKey {
+        address: Addr,
+        expiration: Uint64,
+        type_urls: Vec<String>,
+        contracts: Vec<Addr>,
+        spend_limit: Vec<Coin>,
+    },
+    RevokeSessionKey {
+        address: Addr,
+    },
+    RevokeAllSessionKeys {},
+    /// Replace the guardians allowed to freeze this smart account
+    UpdateGuardians {
+        guardians: Vec<Addr>,
+    },
+    /// Lock this smart account, called by this smart account or one of its guardians
+    Freeze {},
+    /// Unlock this smart account, approved by a majority of its guardians
+    /// or called by this smart account when it has no guardian,
+    /// with a timelock this smart account unfreezing itself is queued,
+    /// a successful recovery also unfreezes it
+    Unfreeze {},
+    /// Set the delay in seconds of the sensitive operations
+    /// lowering the delay is itself delayed by the current delay
+    UpdateTimelock {
+        timelock: u64,
+    },
+    /// Execute a sensitive operation once its delay has passed
+    ExecutePendingAction {
+        id: u64,
+    },
+    /// Cancel a sensitive operation, called by this smart account,
+    /// one of its guardians or one of its active recovery plugins
+    CancelPendingAction {
+        id: u64,
+    },
+    /// Execute messages on behalf of this smart account, called by an active plugin
+    /// the messages must stay in the scope given to the plugin at registration
+    ExecuteFromPlugin {
+        msgs: Vec<CosmosMsg>,
+    },
+}
+
+/// PluginOp is a single plugin operation of `BatchPluginOps`
+#[cw_serde]
+pub enum PluginOp {
+    Register {
+        plugin_address: Addr,
+        config: String,
+        filter: Option<MessageFilter>,
+        scope: Option<PluginScope>,
+    },
+    Unregister {
+        plugin_address: Addr,
+    },
+    Update {
+        plugin_address: Addr,
+        status: PluginStatus,
+    },
+    UpdateConfig {
+        plugin_address: Addr,
+        config: String,
+    },
+}
+
+impl ExecuteMsg {
+    /// the plugin operations executed by this message
+    pub fn plugin_ops(self) -> Vec<PluginOp> {
+        match self {
+            ExecuteMsg::RegisterPlugin {
+                plugin_address,
+                config,
+                filter,
+                scope,
+            } => vec![PluginOp::Register {
+                plugin_address,
+                config,
+                filter,
+                scope,
+            }],
+            ExecuteMsg::UnregisterPlugin { plugin_address } => {
+                vec![PluginOp::Unregister { plugin_address }]
+            }
+            ExecuteMsg::UpdatePlugin {
+                plugin_address,
+                status,
+            } => vec![PluginOp::Update {
+                plugin_address,
+                status,
+            }],
+            ExecuteMsg::UpdatePluginConfig {
+                plugin_address,
+                config,
+            } => vec![PluginOp::UpdateConfig {
+                plugin_address,
+                config,
+            }],
+            ExecuteMsg::BatchPluginOps { ops } => ops,
+            _ => vec![],
+        }
+    }
 }
 
 /// Message type for `migrate` entry_point
 #[cw_serde]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {
+    // re-check every registered plugin against the plugin manager,
+    // plugins which are unknown, disabled or migrated are suspended,
+    // plugins are checked unless the migration explicitly skips it
+    #[serde(default = "default_check_plugins")]
+    pub check_plugins: bool,
+}
+
+fn default_check_plugins() -> bool {
+    true
+}
 
 /// Message type for `query` entry_point
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+
+    #[returns(PluginResponse)]
+    Plugin { address: String },
+
     #[returns(AllPluginsResponse)]
     AllPlugins {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+
+    #[returns(PluginsByTypeResponse)]
+    PluginsByType {
+        plugin_type: PluginType,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(RecoveryStatusResponse)]
+    RecoveryStatus {},
+
+    #[returns(RecoveryLogResponse)]
+    RecoveryLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    #[returns(ContractVersionResponse)]
+    ContractVersion {},
+
+    #[returns(SessionKeysResponse)]
+    SessionKeys {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(PendingActionsResponse)]
+    PendingActions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Check a transaction with the rules of `after_execute` without executing it
+    #[returns(SimulateExecuteResponse)]
+    SimulateExecute {
+        msgs: Vec<SdkMsg>,
+        call_info: CallInfo,
+        is_authz: bool,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub plugin_manager_addr: Addr,
+    pub plugin_gas_budget: u64,
+    pub recoverable: Option<RecoveryPolicy>,
+    pub session_keys_only: bool,
+    pub guardians: Vec<Addr>,
+    pub frozen: bool,
+    pub timelock: u64,
+}
+
+#[cw_serde]
+pub struct PluginResponse {
+    // None if the plugin is not registered
+    pub plugin: Option<Plugin>,
 }
 
 #[cw_serde]
 pub struct AllPluginsResponse {
     pub plugins: Vec<Plugin>,
 }
+
+#[cw_serde]
+pub struct PluginsByTypeResponse {
+    pub plugins: Vec<Plugin>,
+}
+
+#[cw_serde]
+pub struct RecoveryStatusResponse {
+    // whether this smart account can be recovered
+    pub recoverable: bool,
+    // number of recovery plugins that must approve a recovery
+    pub threshold: Option<u32>,
+    // active recovery plugins taking part in a recovery
+    pub recovery_plugins: Vec<Addr>,
+    // recovery plugins of a legacy interface which do not receive the nonce,
+    // their approvals can be replayed until they are unregistered
+    pub legacy_recovery_plugins: Vec<Addr>,
+    // nonce passed to the recovery plugins in the next recovery
+    pub nonce: u64,
+}
+
+#[cw_serde]
+pub struct RecoveryLogResponse {
+    pub records: Vec<RecoveryRecord>,
+}
+
+#[cw_serde]
+pub struct SessionKeysResponse {
+    pub session_keys: Vec<SessionKey>,
+}
+
+#[cw_serde]
+pub struct PendingActionsResponse {
+    pub actions: Vec<PendingAction>,
+}
+
+#[cw_serde]
+pub struct SimulateExecuteResponse {
+    // whether the smart account and all its plugins would accept the transaction
+    pub valid: bool,
+    // reason of the smart account rejecting the transaction, plugins are not checked then
+    pub reason: Option<String>,
+    pub plugins: Vec<PluginVerdict>,
+}
+
+/// PluginVerdict is the result of `ValidateExecute` of a plugin called in `after_execute`
+#[cw_serde]
+pub struct PluginVerdict {
+    pub plugin_address: Addr,
+    pub valid: bool,
+    pub reason: Option<String>,
+}
+
+#[cw_serde]
+pub struct ContractVersionResponse {
+    pub contract: String,
+    pub version: String,
+}
@@ -0,0 +1,189 @@
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{MsgMultiSend, MsgSend};
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin as ProtoCoin;
+use cosmos_sdk_proto::cosmos::staking::v1beta1::MsgDelegate;
+use cosmos_sdk_proto::cosmwasm::wasm::v1::{
+    MsgExecuteContract, MsgInstantiateContract, MsgInstantiateContract2,
+};
+use cosmos_sdk_proto::ibc::applications::transfer::v1::MsgTransfer;
+use cosmos_sdk_proto::traits::Message;
+use cosmwasm_std::{Addr, Coin, Env, StdError, Storage, Uint128};
+use pyxis_sm::msg::{CallInfo, SdkMsg};
+
+use crate::authz::unwrap_msgs;
+use crate::error::ContractError;
+use crate::state::{SessionKey, CONFIG, SESSION_KEYS};
+
+// messages which can transfer funds out of the smart account
+const BANK_SEND: &str = "/cosmos.bank.v1beta1.MsgSend";
+const BANK_MULTI_SEND: &str = "/cosmos.bank.v1beta1.MsgMultiSend";
+const WASM_EXECUTE: &str = "/cosmwasm.wasm.v1.MsgExecuteContract";
+const WASM_INSTANTIATE: &str = "/cosmwasm.wasm.v1.MsgInstantiateContract";
+const WASM_INSTANTIATE2: &str = "/cosmwasm.wasm.v1.MsgInstantiateContract2";
+const STAKE_DELEGATE: &str = "/cosmos.staking.v1beta1.MsgDelegate";
+const IBC_TRANSFER: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+/// check that an authz transaction stays in the scope of the session key executing it
+/// the session key is the fee payer of the transaction: the chain requires the fee payer
+/// to sign the transaction, so a session key can not be used without its signature,
+/// but a session key paying the fees of a `MsgExec` granted to another key co-signing
+/// the transaction is trusted as if it had executed it
+/// the funds spent by the transaction are added to the spending of the session key
+pub fn check_session_key(
+    storage: &mut dyn Storage,
+    env: &Env,
+    msgs: &[SdkMsg],
+    call_info: &CallInfo,
+) -> Result<(), ContractError> {
+    if let Some((address, session_key)) = validate_session_key(storage, env, msgs, call_info)? {
+        SESSION_KEYS.save(storage, &address, &session_key)?;
+    }
+    Ok(())
+}
+
+/// check an authz transaction like `check_session_key` without recording its spending
+/// return the session key executing the transaction with the funds it spends added,
+/// or none if the fee payer is not a session key and authz transactions are not
+/// restricted to session keys
+/// a registered session key is always held to its scope, `session_keys_only`
+/// only rejects the authz transactions of other grantees
+pub fn validate_session_key(
+    storage: &dyn Storage,
+    env: &Env,
+    msgs: &[SdkMsg],
+    call_info: &CallInfo,
+) -> Result<Option<(Addr, SessionKey)>, ContractError> {
+    let address = Addr::unchecked(&call_info.fee_payer);
+    let mut session_key = match SESSION_KEYS.may_load(storage, &address)? {
+        Some(session_key) => session_key,
+        None if CONFIG.load(storage)?.session_keys_only => {
+            return Err(ContractError::NotSessionKey {
+                address: address.to_string(),
+            })
+        }
+        None => return Ok(None),
+    };
+
+    if session_key.expiration.u64() <= env.block.time.seconds() {
+        return Err(ContractError::SessionKeyExpired {
+            address: address.to_string(),
+        });
+    }
+
+    // messages executed through authz `MsgExec` must be in the scope of the session key too
+    let msgs: Vec<SdkMsg> = unwrap_msgs(msgs)?
+        .into_iter()
+        .map(|unwrapped_msg| unwrapped_msg.msg)
+        .collect();
+
+    for msg in &msgs {
+        if !session_key.type_urls.is_empty() && !session_key.type_urls.contains(&msg.type_url) {
+            return Err(ContractError::SessionKeyMessageNotAllowed {
+                type_url: msg.type_url.clone(),
+            });
+        }
+
+        if !session_key.contracts.is_empty() && msg.type_url == WASM_EXECUTE {
+            let msg_exec = decode::<MsgExecuteContract>(msg)?;
+            if !session_key
+                .contracts
+                .iter()
+                .any(|contract| contract.as_str() == msg_exec.contract)
+            {
+                return Err(ContractError::SessionKeyContractNotAllowed {
+                    contract: msg_exec.contract,
+                });
+            }
+        }
+    }
+
+    // denoms not in the spend limit of the session key can not be spent
+    for coin in spent_coins(&msgs)? {
+        let limit = session_key
+            .spend_limit
+            .iter()
+            .find(|limit| limit.denom == coin.denom)
+            .map(|limit| limit.amount)
+            .unwrap_or_default();
+
+        let idx = match session_key
+            .spent
+            .iter()
+            .position(|spent| spent.denom == coin.denom)
+        {
+            Some(idx) => idx,
+            None => {
+                session_key.spent.push(Coin {
+                    denom: coin.denom.clone(),
+                    amount: Uint128::zero(),
+                });
+                session_key.spent.len() - 1
+            }
+        };
+        let spent = &mut session_key.spent[idx];
+        spent.amount = spent
+            .amount
+            .checked_add(coin.amount)
+            .map_err(StdError::from)?;
+
+        if spent.amount > limit {
+            return Err(ContractError::SessionKeySpendLimitExceeded {
+                denom: coin.denom,
+                limit,
+                spent: spent.amount,
+            });
+        }
+    }
+
+    Ok(Some((address, session_key)))
+}
+
+/// sum the funds transferred out of the smart account by the messages
+fn spent_coins(msgs: &[SdkMsg]) -> Result<Vec<Coin>, ContractError> {
+    let mut coins: Vec<Coin> = Vec::new();
+    for msg in msgs {
+        let amount: Vec<ProtoCoin> = match msg.type_url.as_str() {
+            BANK_SEND => decode::<MsgSend>(msg)?.amount,
+            BANK_MULTI_SEND => decode::<MsgMultiSend>(msg)?
+                .inputs
+                .into_iter()
+                .flat_map(|input| input.coins)
+                .collect(),
+            WASM_EXECUTE => decode::<MsgExecuteContract>(msg)?.funds,
+            WASM_INSTANTIATE => decode::<MsgInstantiateContract>(msg)?.funds,
+            WASM_INSTANTIATE2 => decode::<MsgInstantiateContract2>(msg)?.funds,
+            STAKE_DELEGATE => decode::<MsgDelegate>(msg)?.amount.into_iter().collect(),
+            IBC_TRANSFER => decode::<MsgTransfer>(msg)?.token.into_iter().collect(),
+            _ => vec![],
+        };
+
+        for coin in amount {
+            let amount =
+                coin.amount
+                    .parse::<u128>()
+                    .map_err(|_| ContractError::MalformedMessage {
+                        type_url: msg.type_url.clone(),
+                    })?;
+
+            match coins.iter_mut().find(|c| c.denom == coin.denom) {
+                Some(c) => {
+                    c.amount = c
+                        .amount
+                        .checked_add(Uint128::new(amount))
+                        .map_err(StdError::from)?
+                }
+                None => coins.push(Coin {
+                    denom: coin.denom,
+                    amount: Uint128::new(amount),
+                }),
+            }
+        }
+    }
+
+    Ok(coins)
+}
+
+fn decode<T: Message + Default>(msg: &SdkMsg) -> Result<T, ContractError> {
+    T::decode(msg.value.as_slice()).map_err(|_| ContractError::MalformedMessage {
+        type_url: msg.type_url.clone(),
+    })
+}
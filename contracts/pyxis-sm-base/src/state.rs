@@ -1,18 +1,108 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Coin, HexBinary, Uint64};
 use cw_storage_plus::{Item, Map};
+use pyxis_sm::msg::LEGACY_PLUGIN_INTERFACE_VERSION;
 use pyxis_sm::plugin_manager_msg::PluginType;
 
 #[cw_serde]
 pub struct Config {
     pub plugin_manager_addr: Addr,
-    pub recoverable: bool,
+    // maximum total gas of the plugins called in `pre_execute` and `after_execute`
+    pub plugin_gas_budget: u64,
+    // recoverable is None until a recovery plugin is registered
+    pub recoverable: Option<RecoveryPolicy>,
+    // authz transactions are only allowed from active session keys,
+    // turned on by the smart account with `UpdateConfig`
+    #[serde(default)]
+    pub session_keys_only: bool,
+    // accounts allowed to freeze this smart account
+    #[serde(default)]
+    pub guardians: Vec<Addr>,
+    // a frozen smart account only executes `Unfreeze`, until it is unfrozen or recovered
+    #[serde(default)]
+    pub frozen: bool,
+    // delay in seconds of the sensitive operations, 0 executes them immediately
+    #[serde(default)]
+    pub timelock: u64,
+}
+
+/// RecoveryPolicy is a M-of-N policy over the registered recovery plugins
+#[cw_serde]
+pub struct RecoveryPolicy {
+    // number of recovery plugins that must approve a recovery
+    pub threshold: u32,
+}
+
+/// RecoveryTally keeps track of the replies of recovery plugins
+/// while a recovery is in progress
+#[cw_serde]
+pub struct RecoveryTally {
+    pub threshold: u32,
+    // recovery nonce passed to the recovery plugins
+    pub nonce: u64,
+    pub caller: String,
+    // sha256 hash of the new public key
+    pub pub_key_hash: HexBinary,
+    // recovery plugins which have not replied yet, in dispatching order
+    pub pending: Vec<Addr>,
+    // recovery plugins which approved the recovery
+    pub approvals: Vec<Addr>,
+}
+
+/// RecoveryRecord is an entry of the recovery log, written for every successful recovery
+#[cw_serde]
+pub struct RecoveryRecord {
+    pub nonce: u64,
+    pub height: u64,
+    pub caller: String,
+    // sha256 hash of the new public key
+    pub pub_key_hash: HexBinary,
+    // recovery plugins which approved the recovery
+    pub plugins: Vec<Addr>,
 }
 
 #[cw_serde]
 pub enum PluginStatus {
     Active,
     Inactive,
+    // the plugin contract has been migrated since it was registered,
+    // it is not called anymore until it is registered again
+    Suspended,
+}
+
+#[cw_serde]
+pub enum CallType {
+    All,
+    Direct,
+    Authz,
+}
+
+/// MessageFilter selects the messages of a transaction that are forwarded to a plugin
+#[cw_serde]
+pub struct MessageFilter {
+    // type_urls of the messages to forward, empty means every type_url
+    pub type_urls: Vec<String>,
+    // contracts of the `MsgExecuteContract` messages to forward, empty means every contract
+    pub contracts: Vec<Addr>,
+    // whether the plugin is called for direct transactions, authz transactions or both
+    pub call_type: CallType,
+}
+
+/// PluginScope is what a plugin can execute on behalf of this smart account with `ExecuteFromPlugin`
+#[cw_serde]
+pub struct PluginScope {
+    // types of the messages the plugin can execute
+    pub msg_types: Vec<ScopedMsgType>,
+    // recipients of the bank sends and contracts of the executions, empty means any address
+    pub recipients: Vec<Addr>,
+    // maximum amount of each denom sent in a single `ExecuteFromPlugin`, other denoms can not be sent
+    pub max_amounts: Vec<Coin>,
+}
+
+#[cw_serde]
+pub enum ScopedMsgType {
+    BankSend,
+    WasmExecute,
 }
 
 #[cw_serde]
@@ -22,8 +112,112 @@ pub struct Plugin {
     pub contract_address: Addr,
     pub status: PluginStatus,
     pub config: String,
+    // a plugin without filter receives every message
+    pub filter: Option<MessageFilter>,
+    // gas limit of every call to this plugin, declared by the plugin manager
+    pub max_gas: u64,
+    // code of the plugin contract at registration
+    pub code_id: u64,
+    // checksum of the plugin code, None if the chain does not support the code info query
+    pub checksum: Option<HexBinary>,
+    // version of the plugin interface the hook messages are encoded in
+    #[serde(default = "legacy_interface_version")]
+    pub interface_version: u32,
+    // None if the plugin can not execute messages on behalf of this smart account
+    #[serde(default)]
+    pub scope: Option<PluginScope>,
+    // denoms whose balance changes are reported to this plugin, None until they are queried
+    #[serde(default)]
+    pub tracked_denoms: Option<Vec<String>>,
+}
+
+fn legacy_interface_version() -> u32 {
+    LEGACY_PLUGIN_INTERFACE_VERSION
+}
+
+/// SessionKey is a temporary key allowed to execute messages for this smart account through authz
+#[cw_serde]
+pub struct SessionKey {
+    pub address: Addr,
+    // block time in seconds after which the session key can not be used
+    pub expiration: Uint64,
+    // type_urls of the allowed messages, empty means every type_url
+    pub type_urls: Vec<String>,
+    // contracts of the allowed `MsgExecuteContract` messages, empty means every contract
+    pub contracts: Vec<Addr>,
+    // maximum amount of each denom the session key can spend, other denoms can not be spent
+    pub spend_limit: Vec<Coin>,
+    // amount spent by the session key so far
+    pub spent: Vec<Coin>,
+}
+
+/// SensitiveAction is an operation delayed by the timelock of the smart account
+#[cw_serde]
+pub enum SensitiveAction {
+    UnregisterPlugin {
+        plugin_address: Addr,
+    },
+    DeactivatePlugin {
+        plugin_address: Addr,
+    },
+    UpdatePluginManager {
+        plugin_manager_addr: Addr,
+    },
+    // lowering the timelock is delayed by the current timelock
+    UpdateTimelock {
+        timelock: u64,
+    },
+    UpdatePluginConfig {
+        plugin_address: Addr,
+        config: String,
+    },
+    UpdateGuardians {
+        guardians: Vec<Addr>,
+    },
+    UpdateRecoveryPolicy {
+        threshold: u32,
+    },
+    // this smart account unfreezing itself, guardians can cancel it
+    Unfreeze {},
+}
+
+/// PendingAction is a sensitive action waiting for the timelock to expire
+#[cw_serde]
+pub struct PendingAction {
+    pub id: u64,
+    pub action: SensitiveAction,
+    // block time in seconds after which the action can be executed
+    pub execute_after: Uint64,
+}
+
+/// BalanceSnapshot is the balances of the tracked denoms of a transaction in `pre_execute`
+#[cw_serde]
+pub struct BalanceSnapshot {
+    pub tx_id: u64,
+    pub balances: Vec<Coin>,
+    // plugins whose code was checked in `pre_execute`, `after_execute` does not check them again
+    #[serde(default)]
+    pub verified_plugins: Vec<Addr>,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 // PLUGINS is a map of plugin contract address to Plugin
 pub const PLUGINS: Map<&Addr, Plugin> = Map::new("plugins");
+// SESSION_KEYS is a map of session key address to SessionKey
+pub const SESSION_KEYS: Map<&Addr, SessionKey> = Map::new("session_keys");
+// PENDING_ACTIONS is a map of pending action id to PendingAction
+pub const PENDING_ACTIONS: Map<u64, PendingAction> = Map::new("pending_actions");
+pub const NEXT_ACTION_ID: Item<u64> = Item::new("next_action_id");
+// TX_COUNTER is the id of the last transaction, increased in every `pre_execute`
+pub const TX_COUNTER: Item<u64> = Item::new("tx_counter");
+// BALANCE_SNAPSHOT only exists between `pre_execute` and `after_execute` of a transaction
+pub const BALANCE_SNAPSHOT: Item<BalanceSnapshot> = Item::new("balance_snapshot");
+// RECOVERY_TALLY only exists during the execution of a recovery
+pub const RECOVERY_TALLY: Item<RecoveryTally> = Item::new("recovery_tally");
+// RECOVERY_NONCE is the nonce of the next recovery, missing until the first recovery
+pub const RECOVERY_NONCE: Item<u64> = Item::new("recovery_nonce");
+// RECOVERY_LOG is a map of recovery nonce to RecoveryRecord
+pub const RECOVERY_LOG: Map<u64, RecoveryRecord> = Map::new("recovery_log");
+// UNFREEZE_APPROVALS is the guardians which approved unfreezing this smart account
+// since it was frozen
+pub const UNFREEZE_APPROVALS: Item<Vec<Addr>> = Item::new("unfreeze_approvals");
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::vec;
+
+use cosmwasm_std::{coins, Addr};
+use cw_multi_test::{App, Executor};
+use pyxis_sm::plugin_manager_msg::PluginType;
+use sample_plugin_manager::msg::ExecuteMsg as PluginManagerExecuteMsg;
+
+use crate::error::ContractError;
+use crate::msg::ExecuteMsg;
+use crate::state::{PluginScope, ScopedMsgType};
+use crate::testing::test_setup::{
+    allow_plugin, bank_send_cosmos_msg, mock_app, setup_contracts, SM_ADDRESS,
+};
+
+const RECEIVER: &str = "receiver";
+
+// a smart account holding 1000 uaura with plugin_1 allowed to send up to 100 uaura to RECEIVER
+// plugin_2 is allowed by the plugin manager but not registered
+fn setup_scoped_plugin() -> (App, HashMap<String, Addr>) {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &smart_account, coins(1000, "uaura"))
+    })
+    .unwrap();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_2",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account,
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: "approve".to_string(),
+            filter: None,
+            scope: Some(PluginScope {
+                msg_types: vec![ScopedMsgType::BankSend],
+                recipients: vec![Addr::unchecked(RECEIVER)],
+                max_amounts: coins(100, "uaura"),
+            }),
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    (app, contracts)
+}
+
+#[test]
+fn only_registered_plugins_execute_messages() {
+    let (mut app, contracts) = setup_scoped_plugin();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.execute_contract(
+        Addr::unchecked("stranger"),
+        smart_account,
+        &ExecuteMsg::ExecuteFromPlugin {
+            msgs: vec![bank_send_cosmos_msg(RECEIVER, 50)],
+        },
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::Unauthorized {})
+    ));
+}
+
+#[test]
+fn plugin_executes_messages_in_its_scope() {
+    let (mut app, contracts) = setup_scoped_plugin();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    app.execute_contract(
+        plugin_address,
+        smart_account,
+        &ExecuteMsg::ExecuteFromPlugin {
+            msgs: vec![bank_send_cosmos_msg(RECEIVER, 50)],
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    let balance = app.wrap().query_balance(RECEIVER, "uaura").unwrap();
+    assert_eq!(balance.amount.u128(), 50);
+}
+
+#[test]
+fn plugin_cannot_execute_messages_out_of_its_scope() {
+    let (mut app, contracts) = setup_scoped_plugin();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    // other recipients and amounts over the cap are out of scope
+    for msgs in [
+        vec![bank_send_cosmos_msg("stranger", 50)],
+        vec![
+            bank_send_cosmos_msg(RECEIVER, 60),
+            bank_send_cosmos_msg(RECEIVER, 60),
+        ],
+    ] {
+        let response = app.execute_contract(
+            plugin_address.clone(),
+            smart_account.clone(),
+            &ExecuteMsg::ExecuteFromPlugin { msgs },
+            &vec![],
+        );
+        assert!(matches!(
+            response.unwrap_err().downcast_ref::<ContractError>(),
+            Some(ContractError::PluginScopeExceeded { .. })
+        ));
+    }
+}
+
+#[test]
+fn hooks_of_other_plugins_are_called() {
+    let (mut app, contracts) = setup_scoped_plugin();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_2").unwrap().clone(),
+            config: "reject".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    let response = app.execute_contract(
+        plugin_address,
+        smart_account,
+        &ExecuteMsg::ExecuteFromPlugin {
+            msgs: vec![bank_send_cosmos_msg(RECEIVER, 50)],
+        },
+        &vec![],
+    );
+    assert!(response.is_err());
+    let balance = app.wrap().query_balance(RECEIVER, "uaura").unwrap();
+    assert!(balance.amount.is_zero());
+}
+
+#[test]
+fn frozen_account_does_not_execute_plugin_messages() {
+    let (mut app, contracts) = setup_scoped_plugin();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::Freeze {},
+        &vec![],
+    )
+    .unwrap();
+
+    let response = app.execute_contract(
+        plugin_address,
+        smart_account,
+        &ExecuteMsg::ExecuteFromPlugin {
+            msgs: vec![bank_send_cosmos_msg(RECEIVER, 50)],
+        },
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::AccountFrozen {})
+    ));
+}
+
+#[test]
+fn plugins_disabled_by_plugin_manager_do_not_execute_messages() {
+    let (mut app, contracts) = setup_scoped_plugin();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_manager = contracts.get("plugin_manager").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    let execute_from_plugin = |app: &mut App| {
+        app.execute_contract(
+            plugin_address.clone(),
+            smart_account.clone(),
+            &ExecuteMsg::ExecuteFromPlugin {
+                msgs: vec![bank_send_cosmos_msg(RECEIVER, 10)],
+            },
+            &vec![],
+        )
+    };
+
+    // the plugin manager pauses all plugins
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        plugin_manager.clone(),
+        &PluginManagerExecuteMsg::PauseAll {},
+        &vec![],
+    )
+    .unwrap();
+    assert!(matches!(
+        execute_from_plugin(&mut app)
+            .unwrap_err()
+            .downcast_ref::<ContractError>(),
+        Some(ContractError::PluginDisabled {})
+    ));
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        plugin_manager.clone(),
+        &PluginManagerExecuteMsg::UnpauseAll {},
+        &vec![],
+    )
+    .unwrap();
+    execute_from_plugin(&mut app).unwrap();
+
+    // the plugin manager disallows the plugin
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        plugin_manager,
+        &PluginManagerExecuteMsg::DisallowPlugin {
+            plugin_address: plugin_address.clone(),
+        },
+        &vec![],
+    )
+    .unwrap();
+    assert!(matches!(
+        execute_from_plugin(&mut app)
+            .unwrap_err()
+            .downcast_ref::<ContractError>(),
+        Some(ContractError::PluginDisabled {})
+    ));
+
+    let balance = app.wrap().query_balance(RECEIVER, "uaura").unwrap();
+    assert_eq!(balance.amount.u128(), 10);
+}
@@ -1,12 +1,21 @@
 use std::vec;
 
-use cosmwasm_std::Addr;
-use cw_multi_test::Executor;
-use pyxis_sm::msg::{CallInfo, PyxisSudoMsg};
+use cosmwasm_std::{coins, to_json_vec, Addr, Binary, Deps, Empty, Env, StdError, StdResult};
+use cw_multi_test::{App, AppResponse, ContractWrapper, Executor};
+use pyxis_sm::msg::{v3, CallInfo, PyxisPluginExecuteMsg, PyxisSudoMsg, SdkMsg};
 use pyxis_sm::plugin_manager_msg::PluginType;
-
-use crate::msg::ExecuteMsg;
-use crate::testing::test_setup::{allow_plugin, mock_app, setup_contracts, SM_ADDRESS};
+use sample_plugin::contract::{
+    execute as plugin_execute, instantiate as plugin_instantiate, query as plugin_query,
+};
+use sample_plugin::msg::{InstantiateMsg as PluginInstantiateMsg, QueryMsg as PluginQueryMsg};
+use sample_plugin_manager::msg::ExecuteMsg as PluginManagerExecuteMsg;
+
+use crate::error::ContractError;
+use crate::msg::{AllPluginsResponse, ExecuteMsg, PluginResponse, QueryMsg};
+use crate::state::{CallType, MessageFilter, PluginStatus};
+use crate::testing::test_setup::{
+    allow_plugin, execute_contract, mock_app, nested_execute_contract, setup_contracts, SM_ADDRESS,
+};
 
 #[test]
 fn pre_execute_without_plugin() {
@@ -48,6 +57,8 @@ fn pre_execute_with_a_plugin_always_reject() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "reject".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     )
@@ -87,6 +98,8 @@ fn pre_execute_and_plugin_approve() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "approve".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     )
@@ -133,6 +146,8 @@ fn pre_execute_and_one_plugin_reject() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "approve".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     )
@@ -145,6 +160,8 @@ fn pre_execute_and_one_plugin_reject() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_2").unwrap().clone(),
             config: "reject".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     )
@@ -203,6 +220,8 @@ fn after_execute_and_plugin_reject() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "reject".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     )
@@ -242,6 +261,8 @@ fn after_execute_and_plugin_approve() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "approve".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     )
@@ -259,3 +280,514 @@ fn after_execute_and_plugin_approve() {
     println!("response: {:?}", response);
     assert!(response.is_ok());
 }
+
+#[test]
+fn pre_execute_only_calls_plugin_with_matching_filter() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    // register a rejecting plugin which only checks bank send messages
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: "reject".to_string(),
+            filter: Some(MessageFilter {
+                type_urls: vec!["/cosmos.bank.v1beta1.MsgSend".to_string()],
+                contracts: vec![],
+                call_type: CallType::All,
+            }),
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    // plugin is not called for other messages
+    let response = app.wasm_sudo(
+        contracts.get("smart_account").unwrap().clone(),
+        &PyxisSudoMsg::PreExecute {
+            msgs: vec![SdkMsg {
+                type_url: "/cosmos.staking.v1beta1.MsgDelegate".to_string(),
+                value: Binary::default(),
+            }],
+            call_info: CallInfo::default(),
+            is_authz: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    // plugin is called for bank send messages
+    let response = app.wasm_sudo(
+        contracts.get("smart_account").unwrap().clone(),
+        &PyxisSudoMsg::PreExecute {
+            msgs: vec![SdkMsg {
+                type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+                value: Binary::default(),
+            }],
+            call_info: CallInfo::default(),
+            is_authz: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_err());
+}
+
+#[test]
+fn pre_execute_suspends_migrated_plugin() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    // register plugin 1 to reject
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: "reject".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    // migrate plugin 1 behind the back of the smart account
+    app.migrate_contract(
+        contracts.get("plugin_manager").unwrap().clone(),
+        contracts.get("plugin_1").unwrap().clone(),
+        &Empty {},
+        *code_ids.get("sample_plugin_v2").unwrap(),
+    )
+    .unwrap();
+
+    // the migrated plugin is suspended instead of being called
+    let response = app.wasm_sudo(
+        contracts.get("smart_account").unwrap().clone(),
+        &PyxisSudoMsg::PreExecute {
+            msgs: vec![],
+            call_info: CallInfo::default(),
+            is_authz: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(response
+        .unwrap()
+        .events
+        .iter()
+        .any(|event| event.ty == "wasm-plugin_suspended"));
+
+    let plugins: AllPluginsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("smart_account").unwrap().clone(),
+            &QueryMsg::AllPlugins {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(plugins.plugins[0].status, PluginStatus::Suspended);
+}
+
+#[test]
+fn after_execute_checks_messages_nested_in_msg_exec() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: plugin_address.clone(),
+            config: "approve".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    // plugin messages can not be executed directly, even through `MsgExec`
+    let response = app.wasm_sudo(
+        smart_account.clone(),
+        &PyxisSudoMsg::AfterExecute {
+            msgs: vec![nested_execute_contract(
+                plugin_address.as_str(),
+                to_json_vec(&PyxisPluginExecuteMsg::Unregister {}).unwrap(),
+            )],
+            call_info: CallInfo::default(),
+            is_authz: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::NotAllowedAction {})
+    ));
+
+    // hook messages of the older versions of the plugin interface are rejected too
+    let response = app.wasm_sudo(
+        smart_account.clone(),
+        &PyxisSudoMsg::AfterExecute {
+            msgs: vec![execute_contract(
+                plugin_address.as_str(),
+                to_json_vec(&v3::PyxisPluginExecuteMsg::AfterExecute {
+                    msgs: vec![],
+                    call_info: CallInfo::default(),
+                    is_authz: false,
+                    tx_id: 1,
+                })
+                .unwrap(),
+            )],
+            call_info: CallInfo::default(),
+            is_authz: false,
+        },
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::NotAllowedAction {})
+    ));
+
+    // plugins of the smart account can not be managed through `MsgExec`
+    let response = app.wasm_sudo(
+        smart_account.clone(),
+        &PyxisSudoMsg::AfterExecute {
+            msgs: vec![nested_execute_contract(
+                smart_account.as_str(),
+                to_json_vec(&ExecuteMsg::UnregisterPlugin { plugin_address }).unwrap(),
+            )],
+            call_info: CallInfo::default(),
+            is_authz: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::AuthzNotAllowed {})
+    ));
+}
+
+#[test]
+fn hooks_of_a_transaction_share_tx_id() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let tx_id = |response: &AppResponse| {
+        response
+            .events
+            .iter()
+            .flat_map(|event| event.attributes.iter())
+            .find(|attribute| attribute.key == "tx_id")
+            .map(|attribute| attribute.value.clone())
+    };
+
+    for expected_tx_id in ["1", "2"] {
+        let response = app
+            .wasm_sudo(
+                smart_account.clone(),
+                &PyxisSudoMsg::PreExecute {
+                    msgs: vec![],
+                    call_info: CallInfo::default(),
+                    is_authz: false,
+                },
+            )
+            .unwrap();
+        assert_eq!(tx_id(&response), Some(expected_tx_id.to_string()));
+
+        let response = app
+            .wasm_sudo(
+                smart_account.clone(),
+                &PyxisSudoMsg::AfterExecute {
+                    msgs: vec![],
+                    call_info: CallInfo::default(),
+                    is_authz: false,
+                },
+            )
+            .unwrap();
+        assert_eq!(tx_id(&response), Some(expected_tx_id.to_string()));
+    }
+}
+
+#[test]
+fn after_execute_reports_balance_changes() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &smart_account, coins(1000, "uaura"))
+    })
+    .unwrap();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: "approve".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    app.wasm_sudo(
+        smart_account.clone(),
+        &PyxisSudoMsg::PreExecute {
+            msgs: vec![],
+            call_info: CallInfo::default(),
+            is_authz: false,
+        },
+    )
+    .unwrap();
+
+    // the denoms tracked by the plugin are kept for the next transactions
+    let plugin: PluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account.clone(),
+            &QueryMsg::Plugin {
+                address: contracts.get("plugin_1").unwrap().to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        plugin.plugin.unwrap().tracked_denoms,
+        Some(vec!["uaura".to_string()])
+    );
+
+    // the transaction sends tokens of the smart account
+    app.send_tokens(
+        smart_account.clone(),
+        Addr::unchecked("receiver"),
+        &coins(100, "uaura"),
+    )
+    .unwrap();
+
+    // the sample plugin tracks uaura and logs the balance changes it receives
+    let response = app
+        .wasm_sudo(
+            smart_account,
+            &PyxisSudoMsg::AfterExecute {
+                msgs: vec![],
+                call_info: CallInfo::default(),
+                is_authz: false,
+            },
+        )
+        .unwrap();
+    assert!(response
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .any(|attribute| attribute.key == "balance_change" && attribute.value == "uaura:1000:900"));
+}
+
+// a sample plugin which fails to answer the `TrackedDenoms` query
+fn failing_tracked_denoms_query(deps: Deps, env: Env, msg: PluginQueryMsg) -> StdResult<Binary> {
+    match msg {
+        PluginQueryMsg::TrackedDenoms { .. } => {
+            Err(StdError::generic_err("tracked denoms are not available"))
+        }
+        msg => plugin_query(deps, env, msg),
+    }
+}
+
+#[test]
+fn tracked_denoms_are_not_kept_when_the_query_fails() {
+    let (mut app, mut code_ids) = mock_app();
+
+    let mut contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let failing_plugin_code_id = app.store_code(Box::new(ContractWrapper::new(
+        plugin_execute,
+        plugin_instantiate,
+        failing_tracked_denoms_query,
+    )));
+    let failing_plugin = app
+        .instantiate_contract(
+            failing_plugin_code_id,
+            Addr::unchecked(SM_ADDRESS),
+            &PluginInstantiateMsg {},
+            &vec![],
+            "failing plugin",
+            Some(contracts.get("plugin_manager").unwrap().to_string()),
+        )
+        .unwrap();
+    code_ids.insert("failing_plugin", failing_plugin_code_id);
+    contracts.insert("failing_plugin".to_string(), failing_plugin.clone());
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "failing_plugin",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: failing_plugin.clone(),
+            config: "approve".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    let response = app.wasm_sudo(
+        smart_account.clone(),
+        &PyxisSudoMsg::PreExecute {
+            msgs: vec![],
+            call_info: CallInfo::default(),
+            is_authz: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    // the plugin is queried again in the next transaction
+    let plugin: PluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account,
+            &QueryMsg::Plugin {
+                address: failing_plugin.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(plugin.plugin.unwrap().tracked_denoms, None);
+}
+
+#[test]
+fn plugins_disabled_by_plugin_manager_are_not_called() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_manager = contracts.get("plugin_manager").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: plugin_address.clone(),
+            config: "config".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    let plugin_called = |app: &mut App| {
+        app.wasm_sudo(
+            smart_account.clone(),
+            &PyxisSudoMsg::AfterExecute {
+                msgs: vec![],
+                call_info: CallInfo::default(),
+                is_authz: false,
+            },
+        )
+        .unwrap()
+        .events
+        .iter()
+        .any(|event| {
+            event.ty == "execute"
+                && event
+                    .attributes
+                    .iter()
+                    .any(|attribute| attribute.value == plugin_address.as_str())
+        })
+    };
+    assert!(plugin_called(&mut app));
+
+    // the plugin manager pauses all plugins
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        plugin_manager.clone(),
+        &PluginManagerExecuteMsg::PauseAll {},
+        &vec![],
+    )
+    .unwrap();
+    assert!(!plugin_called(&mut app));
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        plugin_manager.clone(),
+        &PluginManagerExecuteMsg::UnpauseAll {},
+        &vec![],
+    )
+    .unwrap();
+    assert!(plugin_called(&mut app));
+
+    // the plugin manager disallows the plugin
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        plugin_manager,
+        &PluginManagerExecuteMsg::DisallowPlugin {
+            plugin_address: plugin_address.clone(),
+        },
+        &vec![],
+    )
+    .unwrap();
+    assert!(!plugin_called(&mut app));
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::vec;
+
+use cosmwasm_std::{to_json_vec, Addr};
+use cw_multi_test::{App, Executor};
+use pyxis_sm::msg::SdkMsg;
+
+use crate::error::ContractError;
+use crate::msg::{ConfigResponse, ExecuteMsg, PendingActionsResponse, QueryMsg};
+use crate::state::SensitiveAction;
+use crate::testing::test_setup::{
+    after_execute, bank_send, execute_contract, mock_app, setup_contracts, SM_ADDRESS,
+};
+
+const GUARDIANS: [&str; 3] = ["guardian_1", "guardian_2", "guardian_3"];
+
+// a smart account with three guardians, frozen by the first guardian
+fn setup_frozen_account() -> (App, HashMap<String, Addr>) {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::UpdateGuardians {
+            guardians: GUARDIANS
+                .iter()
+                .map(|guardian| Addr::unchecked(*guardian))
+                .collect(),
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(GUARDIANS[0]),
+        smart_account,
+        &ExecuteMsg::Freeze {},
+        &vec![],
+    )
+    .unwrap();
+
+    (app, contracts)
+}
+
+fn unfreeze() -> SdkMsg {
+    execute_contract(SM_ADDRESS, to_json_vec(&ExecuteMsg::Unfreeze {}).unwrap())
+}
+
+fn is_frozen(app: &App, smart_account: &Addr) -> bool {
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(smart_account.clone(), &QueryMsg::Config {})
+        .unwrap();
+    config.frozen
+}
+
+#[test]
+fn only_guardians_can_freeze() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.execute_contract(
+        Addr::unchecked("stranger"),
+        smart_account.clone(),
+        &ExecuteMsg::Freeze {},
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::Unauthorized {})
+    ));
+    assert!(!is_frozen(&app, &smart_account));
+}
+
+#[test]
+fn frozen_account_only_executes_unfreeze() {
+    let (mut app, contracts) = setup_frozen_account();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.wasm_sudo(
+        smart_account.clone(),
+        &after_execute(vec![bank_send("receiver", 10)]),
+    );
+    println!("response: {:?}", response);
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::AccountFrozen {})
+    ));
+
+    // unfreeze can not be mixed with other messages
+    let response = app.wasm_sudo(
+        smart_account.clone(),
+        &after_execute(vec![unfreeze(), bank_send("receiver", 10)]),
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::AccountFrozen {})
+    ));
+
+    let response = app.wasm_sudo(smart_account, &after_execute(vec![unfreeze()]));
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+}
+
+#[test]
+fn majority_of_guardians_unfreezes() {
+    let (mut app, contracts) = setup_frozen_account();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    // approving twice does not count twice
+    for _ in 0..2 {
+        app.execute_contract(
+            Addr::unchecked(GUARDIANS[0]),
+            smart_account.clone(),
+            &ExecuteMsg::Unfreeze {},
+            &vec![],
+        )
+        .unwrap();
+        assert!(is_frozen(&app, &smart_account));
+    }
+
+    app.execute_contract(
+        Addr::unchecked(GUARDIANS[1]),
+        smart_account.clone(),
+        &ExecuteMsg::Unfreeze {},
+        &vec![],
+    )
+    .unwrap();
+    assert!(!is_frozen(&app, &smart_account));
+
+    let response = app.wasm_sudo(
+        smart_account,
+        &after_execute(vec![bank_send("receiver", 10)]),
+    );
+    assert!(response.is_ok());
+}
+
+#[test]
+fn strangers_cannot_unfreeze() {
+    let (mut app, contracts) = setup_frozen_account();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.execute_contract(
+        Addr::unchecked("stranger"),
+        smart_account.clone(),
+        &ExecuteMsg::Unfreeze {},
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::Unauthorized {})
+    ));
+    assert!(is_frozen(&app, &smart_account));
+}
+
+#[test]
+fn account_with_guardians_cannot_unfreeze_itself() {
+    let (mut app, contracts) = setup_frozen_account();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::Unfreeze {},
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::UnfreezeNotAllowed {})
+    ));
+    assert!(is_frozen(&app, &smart_account));
+}
+
+#[test]
+fn account_without_guardians_unfreezes_itself() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::Freeze {},
+        &vec![],
+    )
+    .unwrap();
+    assert!(is_frozen(&app, &smart_account));
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::Unfreeze {},
+        &vec![],
+    )
+    .unwrap();
+    assert!(!is_frozen(&app, &smart_account));
+}
+
+#[test]
+fn freeze_drops_queued_unfreeze() {
+    let (mut app, contracts) = setup_frozen_account();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    // setting the timelock applies immediately
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::UpdateTimelock { timelock: 86400 },
+        &vec![],
+    )
+    .unwrap();
+
+    // the smart account queues its unfreeze and an update of its guardians
+    for msg in [
+        ExecuteMsg::Unfreeze {},
+        ExecuteMsg::UpdateGuardians {
+            guardians: vec![Addr::unchecked(GUARDIANS[0])],
+        },
+    ] {
+        app.execute_contract(
+            Addr::unchecked(SM_ADDRESS),
+            smart_account.clone(),
+            &msg,
+            &vec![],
+        )
+        .unwrap();
+    }
+
+    // the guardians unfreeze the smart account before the timelock ends
+    for guardian in &GUARDIANS[..2] {
+        app.execute_contract(
+            Addr::unchecked(*guardian),
+            smart_account.clone(),
+            &ExecuteMsg::Unfreeze {},
+            &vec![],
+        )
+        .unwrap();
+    }
+    assert!(!is_frozen(&app, &smart_account));
+
+    app.execute_contract(
+        Addr::unchecked(GUARDIANS[0]),
+        smart_account.clone(),
+        &ExecuteMsg::Freeze {},
+        &vec![],
+    )
+    .unwrap();
+
+    // the queued unfreeze can not lift the new freeze, the other queued actions are kept
+    let pending_actions: PendingActionsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account,
+            &QueryMsg::PendingActions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(pending_actions.actions.len(), 1);
+    assert!(matches!(
+        pending_actions.actions[0].action,
+        SensitiveAction::UpdateGuardians { .. }
+    ));
+}
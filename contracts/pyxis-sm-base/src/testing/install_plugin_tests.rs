@@ -0,0 +1,293 @@
+use cosmwasm_std::{instantiate2_address, to_json_binary, Addr, Api, Empty};
+use cw_multi_test_instantiate2::addons::{MockAddressGenerator, MockApiBech32};
+use cw_multi_test_instantiate2::{
+    no_init, App, AppBuilder, BankKeeper, Contract, ContractWrapper, Executor, FailingModule,
+    WasmKeeper,
+};
+use pyxis_sm::plugin_manager_msg::{
+    PluginAccountsResponse, PluginResponse as PMPluginResponse, PluginType,
+    QueryMsg as PluginManagerQueryMsg,
+};
+use sample_plugin::contract::{
+    execute as plugin_execute, instantiate as plugin_instantiate, query as plugin_query,
+};
+use sample_plugin::msg::InstantiateMsg as PluginInstantiateMsg;
+use sample_plugin_manager::contract::{
+    execute as plugin_manager_execute, instantiate as plugin_manager_instantiate,
+    query as plugin_manager_query,
+};
+use sample_plugin_manager::msg::{
+    ExecuteMsg as PluginManagerExecuteMsg, InstantiateMsg as PluginManagerInstantiateMsg,
+};
+use sample_plugin_manager::state::PluginTemplate;
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, PluginResponse, QueryMsg};
+
+// multi-test 0.13 of the other tests does not execute `Instantiate2`,
+// plugins are installed with the bech32 addresses of a later multi-test version
+type InstallApp = App<
+    BankKeeper,
+    MockApiBech32,
+    cosmwasm_std::testing::MockStorage,
+    FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+>;
+
+struct Contracts {
+    smart_account: Addr,
+    plugin_manager: Addr,
+    plugin_code_id: u64,
+}
+
+fn smart_account_code() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query).with_reply(reply))
+}
+
+fn sample_plugin_code() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        plugin_execute,
+        plugin_instantiate,
+        plugin_query,
+    ))
+}
+
+fn sample_plugin_manager_code() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        plugin_manager_execute,
+        plugin_manager_instantiate,
+        plugin_manager_query,
+    ))
+}
+
+// a smart account whose plugin manager has a template for the code of sample plugin
+fn setup_template() -> (InstallApp, Contracts) {
+    let mut app = AppBuilder::default()
+        .with_api(MockApiBech32::new("aura"))
+        .with_wasm(WasmKeeper::default().with_address_generator(MockAddressGenerator))
+        .build(no_init);
+
+    let smart_account_code_id = app.store_code(smart_account_code());
+    let plugin_manager_code_id = app.store_code(sample_plugin_manager_code());
+    let plugin_code_id = app.store_code(sample_plugin_code());
+
+    let owner = app.api().addr_make("owner");
+    let plugin_manager = app
+        .instantiate_contract(
+            plugin_manager_code_id,
+            owner.clone(),
+            &PluginManagerInstantiateMsg {
+                owner: owner.to_string(),
+            },
+            &[],
+            "plugin manager",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        plugin_manager.clone(),
+        &PluginManagerExecuteMsg::AllowPluginTemplate {
+            template: PluginTemplate {
+                name: "sample plugin".to_string(),
+                plugin_type: PluginType::Other,
+                code_id: plugin_code_id,
+                version: "v0.1.0".to_string(),
+                enabled: true,
+                max_gas: 1_000_000,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    let smart_account = app
+        .instantiate_contract(
+            smart_account_code_id,
+            owner,
+            &InstantiateMsg {
+                plugin_manager_addr: plugin_manager.clone(),
+                plugin_gas_budget: None,
+                initial_plugins: vec![],
+            },
+            &[],
+            "smart account",
+            None,
+        )
+        .unwrap();
+
+    (
+        app,
+        Contracts {
+            smart_account,
+            plugin_manager,
+            plugin_code_id,
+        },
+    )
+}
+
+fn install_plugin_msg(code_id: u64) -> ExecuteMsg {
+    ExecuteMsg::InstallPlugin {
+        code_id,
+        msg: to_json_binary(&PluginInstantiateMsg {}).unwrap(),
+        config: "config".to_string(),
+        filter: None,
+        scope: None,
+    }
+}
+
+// the instance of a template has the instantiate2 address of the smart account
+// with the code id of the template as salt
+fn predict_plugin_address(app: &InstallApp, smart_account: &Addr, code_id: u64) -> Addr {
+    let checksum = app.wrap().query_wasm_code_info(code_id).unwrap().checksum;
+    let creator = app.api().addr_canonicalize(smart_account.as_str()).unwrap();
+    let address =
+        instantiate2_address(checksum.as_slice(), &creator, &code_id.to_be_bytes()).unwrap();
+    app.api().addr_humanize(&address).unwrap()
+}
+
+fn registered_plugin(app: &InstallApp, smart_account: &Addr, plugin_address: &Addr) -> bool {
+    let response: PluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account.clone(),
+            &QueryMsg::Plugin {
+                address: plugin_address.to_string(),
+            },
+        )
+        .unwrap();
+    response.plugin.is_some()
+}
+
+#[test]
+fn plugin_is_installed_at_its_instantiate2_address() {
+    let (mut app, contracts) = setup_template();
+    let plugin_address =
+        predict_plugin_address(&app, &contracts.smart_account, contracts.plugin_code_id);
+
+    let response = app
+        .execute_contract(
+            contracts.smart_account.clone(),
+            contracts.smart_account.clone(),
+            &install_plugin_msg(contracts.plugin_code_id),
+            &[],
+        )
+        .unwrap();
+    assert!(response
+        .events
+        .iter()
+        .any(|event| event.ty == "instantiate"));
+
+    // the instance is created by the smart account with the plugin manager as admin
+    let contract_info = app
+        .wrap()
+        .query_wasm_contract_info(plugin_address.clone())
+        .unwrap();
+    assert_eq!(contract_info.code_id, contracts.plugin_code_id);
+    assert_eq!(contract_info.creator, contracts.smart_account.to_string());
+    assert_eq!(
+        contract_info.admin,
+        Some(contracts.plugin_manager.to_string())
+    );
+
+    // `PluginInstantiated` allows the instance with the information of the template
+    let plugin_info: PMPluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.plugin_manager.clone(),
+            &PluginManagerQueryMsg::PluginInfo {
+                address: plugin_address.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(plugin_info.name, "sample plugin");
+    assert_eq!(plugin_info.code_id, contracts.plugin_code_id);
+    assert!(plugin_info.enabled);
+
+    // the smart account registered the instance and notified the plugin manager
+    assert!(registered_plugin(
+        &app,
+        &contracts.smart_account,
+        &plugin_address
+    ));
+    let plugin_accounts: PluginAccountsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.plugin_manager,
+            &PluginManagerQueryMsg::PluginAccounts {
+                plugin_address: plugin_address.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        plugin_accounts.accounts,
+        vec![contracts.smart_account.to_string()]
+    );
+}
+
+#[test]
+fn installing_template_again_registers_the_same_instance() {
+    let (mut app, contracts) = setup_template();
+    let plugin_address =
+        predict_plugin_address(&app, &contracts.smart_account, contracts.plugin_code_id);
+
+    app.execute_contract(
+        contracts.smart_account.clone(),
+        contracts.smart_account.clone(),
+        &install_plugin_msg(contracts.plugin_code_id),
+        &[],
+    )
+    .unwrap();
+
+    // a smart account has a single instance of each template
+    let response = app.execute_contract(
+        contracts.smart_account.clone(),
+        contracts.smart_account.clone(),
+        &install_plugin_msg(contracts.plugin_code_id),
+        &[],
+    );
+    assert!(matches!(
+        response
+            .unwrap_err()
+            .root_cause()
+            .downcast_ref::<ContractError>(),
+        Some(ContractError::PluginAlreadyRegistered {})
+    ));
+
+    app.execute_contract(
+        contracts.smart_account.clone(),
+        contracts.smart_account.clone(),
+        &ExecuteMsg::UnregisterPlugin {
+            plugin_address: plugin_address.clone(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert!(!registered_plugin(
+        &app,
+        &contracts.smart_account,
+        &plugin_address
+    ));
+
+    // the instance is registered again without being instantiated again
+    let response = app
+        .execute_contract(
+            contracts.smart_account.clone(),
+            contracts.smart_account.clone(),
+            &install_plugin_msg(contracts.plugin_code_id),
+            &[],
+        )
+        .unwrap();
+    assert!(!response
+        .events
+        .iter()
+        .any(|event| event.ty == "instantiate"));
+    assert!(registered_plugin(
+        &app,
+        &contracts.smart_account,
+        &plugin_address
+    ));
+}
@@ -0,0 +1,367 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier};
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Binary, ContractInfoResponse, ContractResult, MemoryStorage,
+    OwnedDeps, SystemError, SystemResult, WasmQuery,
+};
+use cw2::{get_contract_version, set_contract_version};
+use pyxis_sm::msg::LEGACY_PLUGIN_INTERFACE_VERSION;
+use pyxis_sm::plugin_manager_msg::{
+    PluginResponse as PMPluginResponse, PluginType, QueryMsg as PMQueryMsg,
+};
+
+use crate::contract::{migrate, query};
+use crate::error::ContractError;
+use crate::migrations::{
+    LegacyConfig, LegacyConfigV0_2, LegacyPlugin, LegacyPluginV0_2, LEGACY_CONFIG,
+    LEGACY_CONFIG_V0_2, LEGACY_PLUGINS, LEGACY_PLUGINS_V0_2, LEGACY_PLUGIN_MAX_GAS,
+};
+use crate::msg::{MigrateMsg, QueryMsg, RecoveryStatusResponse};
+use crate::state::{PluginStatus, RecoveryPolicy, CONFIG, PLUGINS};
+
+const CONTRACT_NAME: &str = "crates.io:pyxis-sm-base";
+
+// the plugin manager allows code 4 of "recovery_plugin", which is now running code 5,
+// disables "disabled_plugin" and fails to answer for any other plugin
+fn mock_querier(deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>) {
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::ContractInfo { .. } => SystemResult::Ok(ContractResult::Ok(
+            to_json_binary(&ContractInfoResponse::new(5, "creator")).unwrap(),
+        )),
+        WasmQuery::Smart { msg, .. }
+            if from_json::<PMQueryMsg>(msg).ok()
+                == Some(PMQueryMsg::PluginInfo {
+                    address: "recovery_plugin".to_string(),
+                }) =>
+        {
+            // response of a plugin manager from before plugins declared their max gas
+            SystemResult::Ok(ContractResult::Ok(Binary::from(
+                br#"{"name":"recovery plugin","plugin_type":"recovery","version":"v0.1.0","address":"recovery_plugin","code_id":4,"enabled":true}"#
+                    .as_slice(),
+            )))
+        }
+        WasmQuery::Smart { msg, .. }
+            if from_json::<PMQueryMsg>(msg).ok()
+                == Some(PMQueryMsg::PluginInfo {
+                    address: "disabled_plugin".to_string(),
+                }) =>
+        {
+            SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&PMPluginResponse {
+                    name: "disabled plugin".to_string(),
+                    plugin_type: PluginType::Other,
+                    version: "v0.1.0".to_string(),
+                    address: "disabled_plugin".to_string(),
+                    code_id: 5,
+                    enabled: false,
+                    max_gas: 300_000,
+                })
+                .unwrap(),
+            ))
+        }
+        _ => SystemResult::Err(SystemError::Unknown {}),
+    });
+}
+
+#[test]
+fn migrate_msg_checks_plugins_by_default() {
+    let msg: MigrateMsg = from_json(b"{}").unwrap();
+    assert!(msg.check_plugins);
+}
+
+#[test]
+fn legacy_plugin_response_has_legacy_max_gas() {
+    let plugin: PMPluginResponse = from_json(
+        br#"{"name":"plugin","plugin_type":"other","version":"v0.1.0","address":"plugin","code_id":1,"enabled":true}"#,
+    )
+    .unwrap();
+    assert_eq!(plugin.max_gas, LEGACY_PLUGIN_MAX_GAS);
+}
+
+#[test]
+fn migrate_from_v0_1_state() {
+    let mut deps = mock_dependencies();
+    mock_querier(&mut deps);
+
+    let plugin_address = Addr::unchecked("recovery_plugin");
+    let unknown_plugin_address = Addr::unchecked("unknown_plugin");
+    let disabled_plugin_address = Addr::unchecked("disabled_plugin");
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+    LEGACY_CONFIG
+        .save(
+            deps.as_mut().storage,
+            &LegacyConfig {
+                plugin_manager_addr: Addr::unchecked("plugin_manager_addr"),
+                recoverable: true,
+            },
+        )
+        .unwrap();
+    for (address, plugin_type) in [
+        (&plugin_address, PluginType::Recovery),
+        (&unknown_plugin_address, PluginType::Other),
+        (&disabled_plugin_address, PluginType::Other),
+    ] {
+        LEGACY_PLUGINS
+            .save(
+                deps.as_mut().storage,
+                address,
+                &LegacyPlugin {
+                    name: address.to_string(),
+                    plugin_type,
+                    contract_address: address.clone(),
+                    status: PluginStatus::Active,
+                    config: "config".to_string(),
+                },
+            )
+            .unwrap();
+    }
+
+    let response = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            check_plugins: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.recoverable, Some(RecoveryPolicy { threshold: 1 }));
+    // the legacy gas limit of the migrated plugins is added to the default budget
+    assert_eq!(
+        config.plugin_gas_budget,
+        2_000_000 + 2 * LEGACY_PLUGIN_MAX_GAS
+    );
+    assert!(!config.session_keys_only);
+    assert_eq!(config.timelock, 0);
+
+    // the code allowed by the plugin manager is recorded, not the running one
+    let plugin = PLUGINS
+        .load(deps.as_ref().storage, &plugin_address)
+        .unwrap();
+    assert_eq!(plugin.status, PluginStatus::Active);
+    assert_eq!(plugin.code_id, 4);
+    assert_eq!(plugin.checksum, None);
+    assert_eq!(plugin.max_gas, LEGACY_PLUGIN_MAX_GAS);
+    assert_eq!(plugin.filter, None);
+    assert_eq!(plugin.interface_version, LEGACY_PLUGIN_INTERFACE_VERSION);
+
+    // a plugin the plugin manager can not answer for keeps its status
+    let plugin = PLUGINS
+        .load(deps.as_ref().storage, &unknown_plugin_address)
+        .unwrap();
+    assert_eq!(plugin.status, PluginStatus::Active);
+    assert_eq!(plugin.code_id, 5);
+
+    // a plugin disabled by the plugin manager is suspended
+    let plugin = PLUGINS
+        .load(deps.as_ref().storage, &disabled_plugin_address)
+        .unwrap();
+    assert_eq!(plugin.status, PluginStatus::Suspended);
+
+    let version = get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn migrate_from_v0_2_state() {
+    let mut deps = mock_dependencies();
+    mock_querier(&mut deps);
+
+    let plugin_address = Addr::unchecked("recovery_plugin");
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.2.0").unwrap();
+    LEGACY_CONFIG_V0_2
+        .save(
+            deps.as_mut().storage,
+            &LegacyConfigV0_2 {
+                plugin_manager_addr: Addr::unchecked("plugin_manager_addr"),
+                plugin_gas_budget: 2_000_000,
+                recoverable: Some(RecoveryPolicy { threshold: 1 }),
+            },
+        )
+        .unwrap();
+    LEGACY_PLUGINS_V0_2
+        .save(
+            deps.as_mut().storage,
+            &plugin_address,
+            &LegacyPluginV0_2 {
+                name: "recovery plugin".to_string(),
+                plugin_type: PluginType::Recovery,
+                contract_address: plugin_address.clone(),
+                status: PluginStatus::Active,
+                config: "config".to_string(),
+                filter: None,
+                max_gas: 500_000,
+                code_id: 5,
+                checksum: None,
+            },
+        )
+        .unwrap();
+
+    let response = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            check_plugins: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.plugin_gas_budget, 2_000_000);
+    assert_eq!(config.recoverable, Some(RecoveryPolicy { threshold: 1 }));
+    assert!(config.guardians.is_empty());
+    assert!(!config.frozen);
+
+    let plugin = PLUGINS
+        .load(deps.as_ref().storage, &plugin_address)
+        .unwrap();
+    assert_eq!(plugin.code_id, 5);
+    assert_eq!(plugin.max_gas, 500_000);
+    assert_eq!(plugin.interface_version, LEGACY_PLUGIN_INTERFACE_VERSION);
+    assert_eq!(plugin.scope, None);
+
+    // the migrated recovery plugin does not receive the recovery nonce
+    let recovery_status: RecoveryStatusResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::RecoveryStatus {}).unwrap()).unwrap();
+    assert_eq!(
+        recovery_status.recovery_plugins,
+        vec![plugin_address.clone()]
+    );
+    assert_eq!(
+        recovery_status.legacy_recovery_plugins,
+        vec![plugin_address]
+    );
+}
+
+#[test]
+fn check_plugins_keeps_plugins_the_plugin_manager_can_not_answer_for() {
+    let mut deps = mock_dependencies();
+    mock_querier(&mut deps);
+
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.2.0").unwrap();
+    LEGACY_CONFIG_V0_2
+        .save(
+            deps.as_mut().storage,
+            &LegacyConfigV0_2 {
+                plugin_manager_addr: Addr::unchecked("plugin_manager_addr"),
+                plugin_gas_budget: 2_000_000,
+                recoverable: Some(RecoveryPolicy { threshold: 1 }),
+            },
+        )
+        .unwrap();
+    for (address, plugin_type) in [
+        ("recovery_plugin", PluginType::Recovery),
+        ("unknown_plugin", PluginType::Other),
+        ("disabled_plugin", PluginType::Other),
+    ] {
+        LEGACY_PLUGINS_V0_2
+            .save(
+                deps.as_mut().storage,
+                &Addr::unchecked(address),
+                &LegacyPluginV0_2 {
+                    name: address.to_string(),
+                    plugin_type,
+                    contract_address: Addr::unchecked(address),
+                    status: PluginStatus::Active,
+                    config: "config".to_string(),
+                    filter: None,
+                    max_gas: 500_000,
+                    code_id: 4,
+                    checksum: None,
+                },
+            )
+            .unwrap();
+    }
+
+    let response = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            check_plugins: true,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    // the max gas declared by the plugin manager replaces the stored one
+    let plugin = PLUGINS
+        .load(deps.as_ref().storage, &Addr::unchecked("recovery_plugin"))
+        .unwrap();
+    assert_eq!(plugin.status, PluginStatus::Active);
+    assert_eq!(plugin.max_gas, LEGACY_PLUGIN_MAX_GAS);
+
+    let plugin = PLUGINS
+        .load(deps.as_ref().storage, &Addr::unchecked("unknown_plugin"))
+        .unwrap();
+    assert_eq!(plugin.status, PluginStatus::Active);
+    assert_eq!(plugin.max_gas, 500_000);
+
+    let plugin = PLUGINS
+        .load(deps.as_ref().storage, &Addr::unchecked("disabled_plugin"))
+        .unwrap();
+    assert_eq!(plugin.status, PluginStatus::Suspended);
+
+    // the account is still recoverable
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.recoverable, Some(RecoveryPolicy { threshold: 1 }));
+}
+
+#[test]
+fn cannot_migrate_to_older_version() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+    let response = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            check_plugins: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(matches!(
+        response,
+        Err(ContractError::MigrationDowngrade { from, .. }) if from == "99.0.0"
+    ));
+}
+
+#[test]
+fn cannot_migrate_from_another_contract() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, "crates.io:another-contract", "0.1.0").unwrap();
+
+    let response = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            check_plugins: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(matches!(
+        response,
+        Err(ContractError::InvalidMigrationContract { contract })
+            if contract == "crates.io:another-contract"
+    ));
+}
+
+#[test]
+fn cannot_migrate_from_invalid_version() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1").unwrap();
+
+    let response = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            check_plugins: false,
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(matches!(
+        response,
+        Err(ContractError::InvalidVersion { version }) if version == "0.1"
+    ));
+}
@@ -1,5 +1,12 @@
 #[cfg(test)]
+pub mod execute_from_plugin_tests;
 pub mod execute_tests;
+pub mod freeze_tests;
+pub mod install_plugin_tests;
+pub mod migrate_tests;
 pub mod plugin_tests;
+pub mod query_tests;
 pub mod recovery_tests;
+pub mod session_key_tests;
 pub mod test_setup;
+pub mod timelock_tests;
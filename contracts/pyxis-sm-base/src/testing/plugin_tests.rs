@@ -1,12 +1,24 @@
 use std::vec;
 
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::Addr;
-use cw_multi_test::Executor;
-use pyxis_sm::plugin_manager_msg::PluginType;
+use cosmwasm_std::{to_json_binary, Addr, Empty, Reply, SubMsgResult};
+use cw_multi_test::{App, Executor};
+use pyxis_sm::msg::{CallInfo, PyxisSudoMsg, PLUGIN_INTERFACE_VERSION};
+use pyxis_sm::plugin_manager_msg::{
+    PluginAccountsResponse, PluginResponse as PMPluginResponse, PluginType,
+    QueryMsg as PluginManagerQueryMsg,
+};
+use sample_plugin_manager::msg::{
+    ExecuteMsg as PluginManagerExecuteMsg, InstantiateMsg as PluginManagerInstantiateMsg,
+};
 
-use crate::contract::instantiate;
-use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::contract::{instantiate, reply};
+use crate::error::ContractError;
+use crate::msg::{
+    AllPluginsResponse, ExecuteMsg, InitialPlugin, InstantiateMsg, PluginOp, PluginResponse,
+    QueryMsg,
+};
+use crate::state::PluginStatus;
 use crate::testing::test_setup::{allow_plugin, mock_app, setup_contracts, SM_ADDRESS};
 
 #[test]
@@ -15,12 +27,94 @@ fn proper_instantiation() {
     let info = mock_info(SM_ADDRESS, &[]);
     let msg = InstantiateMsg {
         plugin_manager_addr: Addr::unchecked("plugin_manager_addr"),
+        plugin_gas_budget: None,
+        initial_plugins: vec![],
     };
     let env = mock_env();
     let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 }
 
+#[test]
+fn reply_with_unknown_id_fails() {
+    let mut deps = mock_dependencies();
+
+    let response = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 99,
+            result: SubMsgResult::Err("error".to_string()),
+        },
+    );
+    assert!(matches!(
+        response,
+        Err(ContractError::UnknownReplyId { id: 99 })
+    ));
+}
+
+#[test]
+fn instantiate_with_initial_plugins() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    let instantiate_msg = |plugin: &str| InstantiateMsg {
+        plugin_manager_addr: contracts.get("plugin_manager").unwrap().clone(),
+        plugin_gas_budget: None,
+        initial_plugins: vec![InitialPlugin {
+            plugin_address: contracts.get(plugin).unwrap().clone(),
+            config: "approve".to_string(),
+        }],
+    };
+
+    // initial plugins are checked against the plugin manager
+    let response = app.instantiate_contract(
+        *code_ids.get("smart_account").unwrap(),
+        Addr::unchecked(SM_ADDRESS),
+        &instantiate_msg("plugin_2"),
+        &vec![],
+        "smart account 2",
+        None,
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::PluginNotAllowed {})
+    ));
+
+    let smart_account = app
+        .instantiate_contract(
+            *code_ids.get("smart_account").unwrap(),
+            Addr::unchecked(SM_ADDRESS),
+            &instantiate_msg("plugin_1"),
+            &vec![],
+            "smart account 2",
+            None,
+        )
+        .unwrap();
+
+    let plugins: AllPluginsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account,
+            &QueryMsg::AllPlugins {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(plugins.plugins.len(), 1);
+    assert_eq!(plugins.plugins[0].status, PluginStatus::Active);
+}
+
 #[test]
 fn cannot_register_plugin_without_plugin_manager() {
     let (mut app, code_ids) = mock_app();
@@ -33,11 +127,16 @@ fn cannot_register_plugin_without_plugin_manager() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "config".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     );
     println!("response: {:?}", response);
-    assert!(response.is_err());
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::PluginNotAllowed {})
+    ));
 }
 
 #[test]
@@ -60,11 +159,229 @@ fn register_plugin() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "config".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     );
     println!("response: {:?}", response);
     assert!(response.is_ok());
+
+    // hook messages are encoded in the interface version declared by the plugin
+    let plugin: PluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("smart_account").unwrap().clone(),
+            &QueryMsg::Plugin {
+                address: contracts.get("plugin_1").unwrap().to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        plugin.plugin.unwrap().interface_version,
+        PLUGIN_INTERFACE_VERSION
+    );
+}
+
+#[test]
+fn cannot_register_plugin_while_plugins_are_paused() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let plugin_manager = contracts.get("plugin_manager").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        plugin_manager.clone(),
+        &PluginManagerExecuteMsg::PauseAll {},
+        &vec![],
+    )
+    .unwrap();
+
+    // the pause is not reported as a disabled plugin
+    let plugin_info: PMPluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            plugin_manager,
+            &PluginManagerQueryMsg::PluginInfo {
+                address: contracts.get("plugin_1").unwrap().to_string(),
+            },
+        )
+        .unwrap();
+    assert!(plugin_info.enabled);
+
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: "config".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::PluginDisabled {})
+    ));
+}
+
+#[test]
+fn plugin_manager_indexes_plugin_accounts() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: plugin_address.clone(),
+            config: "config".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    let plugin_accounts = |app: &App| -> PluginAccountsResponse {
+        app.wrap()
+            .query_wasm_smart(
+                contracts.get("plugin_manager").unwrap().clone(),
+                &PluginManagerQueryMsg::PluginAccounts {
+                    plugin_address: plugin_address.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    };
+
+    let response = plugin_accounts(&app);
+    assert_eq!(response.accounts, vec![smart_account.to_string()]);
+    assert_eq!(response.count, 1);
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::UnregisterPlugin {
+            plugin_address: plugin_address.clone(),
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    let response = plugin_accounts(&app);
+    assert!(response.accounts.is_empty());
+    assert_eq!(response.count, 0);
+}
+
+#[test]
+fn plugin_manager_checks_notifications_with_the_smart_account() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_manager = contracts.get("plugin_manager").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    // the smart account has not registered the plugin
+    let response = app.execute_contract(
+        smart_account.clone(),
+        plugin_manager.clone(),
+        &PluginManagerExecuteMsg::PluginRegistered {
+            plugin_address: plugin_address.to_string(),
+        },
+        &vec![],
+    );
+    assert!(matches!(
+        response
+            .unwrap_err()
+            .downcast_ref::<sample_plugin_manager::ContractError>(),
+        Some(sample_plugin_manager::ContractError::PluginNotRegistered { .. })
+    ));
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: plugin_address.clone(),
+            config: "config".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    // the smart account still uses the plugin
+    let response = app.execute_contract(
+        smart_account,
+        plugin_manager,
+        &PluginManagerExecuteMsg::PluginUnregistered {
+            plugin_address: plugin_address.to_string(),
+        },
+        &vec![],
+    );
+    assert!(matches!(
+        response
+            .unwrap_err()
+            .downcast_ref::<sample_plugin_manager::ContractError>(),
+        Some(sample_plugin_manager::ContractError::PluginStillRegistered { .. })
+    ));
+}
+
+#[test]
+fn cannot_install_plugin_without_template() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    // the plugin manager has no template for the code of sample plugin
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::InstallPlugin {
+            code_id: *code_ids.get("sample_plugin").unwrap(),
+            msg: to_json_binary(&Empty {}).unwrap(),
+            config: "config".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::PluginNotAllowed {})
+    ));
 }
 
 #[test]
@@ -87,6 +404,8 @@ fn cannot_register_same_plugin() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "config".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     );
@@ -99,11 +418,16 @@ fn cannot_register_same_plugin() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "config".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     );
     println!("response: {:?}", response);
-    assert!(response.is_err());
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::PluginAlreadyRegistered {})
+    ));
 }
 
 #[test]
@@ -133,6 +457,8 @@ fn can_register_two_plugins() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_1").unwrap().clone(),
             config: "config".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     )
@@ -144,8 +470,310 @@ fn can_register_two_plugins() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("plugin_2").unwrap().clone(),
             config: "config".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    );
+    assert!(response.is_ok());
+}
+
+#[test]
+fn cannot_register_plugin_over_gas_budget() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    // each plugin declares 1_000_000 gas, the default budget only fits 2 plugins
+    for plugin in ["plugin_1", "plugin_2", "plugin_3"] {
+        allow_plugin(&mut app, &contracts, &code_ids, plugin, PluginType::Other);
+    }
+
+    for plugin in ["plugin_1", "plugin_2"] {
+        let response = app.execute_contract(
+            Addr::unchecked(SM_ADDRESS),
+            contracts.get("smart_account").unwrap().clone(),
+            &ExecuteMsg::RegisterPlugin {
+                plugin_address: contracts.get(plugin).unwrap().clone(),
+                config: "config".to_string(),
+                filter: None,
+                scope: None,
+            },
+            &vec![],
+        );
+        assert!(response.is_ok());
+    }
+
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_3").unwrap().clone(),
+            config: "config".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    );
+    println!("response: {:?}", response);
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::PluginNotAllowed {})
+    ));
+}
+
+#[test]
+fn update_plugin_manager_suspends_unknown_plugins() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: "config".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    // a plugin is not a plugin manager
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::UpdateConfig {
+            plugin_manager_addr: Some(contracts.get("plugin_2").unwrap().clone()),
+            plugin_gas_budget: None,
+            session_keys_only: None,
+        },
+        &vec![],
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_err());
+
+    let new_plugin_manager_addr = app
+        .instantiate_contract(
+            *code_ids.get("sample_plugin_manager").unwrap(),
+            Addr::unchecked(SM_ADDRESS),
+            &PluginManagerInstantiateMsg {
+                owner: SM_ADDRESS.to_string(),
+            },
+            &vec![],
+            "sample plugin manager 2",
+            Some(SM_ADDRESS.to_string()),
+        )
+        .unwrap();
+
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::UpdateConfig {
+            plugin_manager_addr: Some(new_plugin_manager_addr),
+            plugin_gas_budget: None,
+            session_keys_only: None,
+        },
+        &vec![],
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    // plugin 1 is not known by the new plugin manager
+    let plugins: AllPluginsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("smart_account").unwrap().clone(),
+            &QueryMsg::AllPlugins {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(plugins.plugins[0].status, PluginStatus::Suspended);
+
+    // the previous plugin manager does not count the smart account anymore
+    let plugin_accounts: PluginAccountsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("plugin_manager").unwrap().clone(),
+            &PluginManagerQueryMsg::PluginAccounts {
+                plugin_address: contracts.get("plugin_1").unwrap().to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(plugin_accounts.accounts.is_empty());
+    assert_eq!(plugin_accounts.count, 0);
+}
+
+#[test]
+fn update_plugin_config() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: "reject".to_string(),
+            filter: None,
+            scope: None,
         },
         &vec![],
+    )
+    .unwrap();
+
+    let pre_execute_msg = PyxisSudoMsg::PreExecute {
+        msgs: vec![],
+        call_info: CallInfo::default(),
+        is_authz: false,
+    };
+    let response = app.wasm_sudo(
+        contracts.get("smart_account").unwrap().clone(),
+        &pre_execute_msg,
+    );
+    assert!(response.is_err());
+
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::UpdatePluginConfig {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: "approve".to_string(),
+        },
+        &vec![],
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    // the plugin approves with its new config
+    let response = app.wasm_sudo(
+        contracts.get("smart_account").unwrap().clone(),
+        &pre_execute_msg,
     );
     assert!(response.is_ok());
 }
+
+#[test]
+fn batch_plugin_ops_are_atomic() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_2",
+        PluginType::Other,
+    );
+
+    // plugin 3 is not allowed by the plugin manager, the whole batch fails
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::BatchPluginOps {
+            ops: vec![
+                PluginOp::Register {
+                    plugin_address: contracts.get("plugin_1").unwrap().clone(),
+                    config: "config".to_string(),
+                    filter: None,
+                    scope: None,
+                },
+                PluginOp::Register {
+                    plugin_address: contracts.get("plugin_3").unwrap().clone(),
+                    config: "config".to_string(),
+                    filter: None,
+                    scope: None,
+                },
+            ],
+        },
+        &vec![],
+    );
+    println!("response: {:?}", response);
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::PluginNotAllowed {})
+    ));
+
+    let plugins: AllPluginsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("smart_account").unwrap().clone(),
+            &QueryMsg::AllPlugins {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(plugins.plugins.is_empty());
+
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::BatchPluginOps {
+            ops: vec![
+                PluginOp::Register {
+                    plugin_address: contracts.get("plugin_1").unwrap().clone(),
+                    config: "config".to_string(),
+                    filter: None,
+                    scope: None,
+                },
+                PluginOp::Register {
+                    plugin_address: contracts.get("plugin_2").unwrap().clone(),
+                    config: "config".to_string(),
+                    filter: None,
+                    scope: None,
+                },
+                PluginOp::UpdateConfig {
+                    plugin_address: contracts.get("plugin_1").unwrap().clone(),
+                    config: "approve".to_string(),
+                },
+            ],
+        },
+        &vec![],
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    let plugins: AllPluginsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("smart_account").unwrap().clone(),
+            &QueryMsg::AllPlugins {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(plugins.plugins.len(), 2);
+}
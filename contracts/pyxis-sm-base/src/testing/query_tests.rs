@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::vec;
+
+use cosmwasm_std::{Addr, Empty};
+use cw_multi_test::{App, Executor};
+use pyxis_sm::msg::CallInfo;
+use pyxis_sm::plugin_manager_msg::PluginType;
+use simple_recovery_plugin::state::RecoveryConfig;
+
+use crate::msg::{
+    ConfigResponse, ContractVersionResponse, ExecuteMsg, PluginResponse, PluginsByTypeResponse,
+    QueryMsg, RecoveryStatusResponse, SimulateExecuteResponse,
+};
+use crate::state::PluginStatus;
+use crate::testing::test_setup::{allow_plugin, mock_app, setup_contracts, SM_ADDRESS};
+
+// a smart account with plugin_1 and a recovery plugin registered
+fn setup_plugins() -> (App, HashMap<String, Addr>) {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "recovery_plugin",
+        PluginType::Recovery,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: "config".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    let recovery_config = RecoveryConfig {
+        smart_account_address: smart_account.clone(),
+        recover_address: Addr::unchecked("recoverer"),
+    };
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account,
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("recovery_plugin").unwrap().clone(),
+            config: serde_json_wasm::to_string(&recovery_config).unwrap(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    (app, contracts)
+}
+
+// a smart account with an approving plugin_1 and a rejecting plugin_2
+fn setup_verdict_plugins() -> (App, HashMap<String, Addr>) {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    for (plugin_name, config) in [("plugin_1", "approve"), ("plugin_2", "reject")] {
+        allow_plugin(
+            &mut app,
+            &contracts,
+            &code_ids,
+            plugin_name,
+            PluginType::Other,
+        );
+        app.execute_contract(
+            Addr::unchecked(SM_ADDRESS),
+            smart_account.clone(),
+            &ExecuteMsg::RegisterPlugin {
+                plugin_address: contracts.get(plugin_name).unwrap().clone(),
+                config: config.to_string(),
+                filter: None,
+                scope: None,
+            },
+            &vec![],
+        )
+        .unwrap();
+    }
+
+    (app, contracts)
+}
+
+fn simulate_execute(app: &App, smart_account: &Addr) -> SimulateExecuteResponse {
+    app.wrap()
+        .query_wasm_smart(
+            smart_account.clone(),
+            &QueryMsg::SimulateExecute {
+                msgs: vec![],
+                call_info: CallInfo::default(),
+                is_authz: false,
+            },
+        )
+        .unwrap()
+}
+
+#[test]
+fn query_config() {
+    let (app, contracts) = setup_plugins();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(smart_account, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(
+        &config.plugin_manager_addr,
+        contracts.get("plugin_manager").unwrap()
+    );
+}
+
+#[test]
+fn query_plugin() {
+    let (app, contracts) = setup_plugins();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let plugin: PluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account.clone(),
+            &QueryMsg::Plugin {
+                address: contracts.get("plugin_1").unwrap().to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(plugin.plugin.unwrap().plugin_type, PluginType::Other);
+
+    // an unregistered plugin is not found
+    let plugin: PluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account,
+            &QueryMsg::Plugin {
+                address: contracts.get("plugin_2").unwrap().to_string(),
+            },
+        )
+        .unwrap();
+    assert!(plugin.plugin.is_none());
+}
+
+#[test]
+fn query_plugins_by_type() {
+    let (app, contracts) = setup_plugins();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let recovery_plugins: PluginsByTypeResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account,
+            &QueryMsg::PluginsByType {
+                plugin_type: PluginType::Recovery,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(recovery_plugins.plugins.len(), 1);
+    assert_eq!(
+        &recovery_plugins.plugins[0].contract_address,
+        contracts.get("recovery_plugin").unwrap()
+    );
+}
+
+#[test]
+fn query_recovery_status() {
+    let (app, contracts) = setup_plugins();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let recovery_status: RecoveryStatusResponse = app
+        .wrap()
+        .query_wasm_smart(smart_account, &QueryMsg::RecoveryStatus {})
+        .unwrap();
+    assert!(recovery_status.recoverable);
+    assert_eq!(recovery_status.threshold, Some(1));
+    assert_eq!(recovery_status.recovery_plugins.len(), 1);
+}
+
+#[test]
+fn query_contract_version() {
+    let (app, contracts) = setup_plugins();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let version: ContractVersionResponse = app
+        .wrap()
+        .query_wasm_smart(smart_account, &QueryMsg::ContractVersion {})
+        .unwrap();
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn simulate_execute_returns_plugin_verdicts() {
+    let (app, contracts) = setup_verdict_plugins();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = simulate_execute(&app, &smart_account);
+    println!("response: {:?}", response);
+    assert!(!response.valid);
+    assert_eq!(response.reason, None);
+    assert_eq!(response.plugins.len(), 2);
+    for verdict in response.plugins {
+        if verdict.plugin_address == contracts.get("plugin_1").unwrap().clone() {
+            assert!(verdict.valid);
+        } else {
+            assert!(!verdict.valid);
+            assert!(verdict.reason.unwrap().contains("reject"));
+        }
+    }
+}
+
+#[test]
+fn simulate_execute_checks_account_rules_before_plugins() {
+    let (mut app, contracts) = setup_verdict_plugins();
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::Freeze {},
+        &vec![],
+    )
+    .unwrap();
+
+    let response = simulate_execute(&app, &smart_account);
+    assert!(!response.valid);
+    assert!(response.reason.is_some());
+    assert!(response.plugins.is_empty());
+}
+
+#[test]
+fn simulate_execute_skips_migrated_plugins() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: plugin_address.clone(),
+            config: "reject".to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    app.migrate_contract(
+        contracts.get("plugin_manager").unwrap().clone(),
+        plugin_address.clone(),
+        &Empty {},
+        *code_ids.get("sample_plugin_v2").unwrap(),
+    )
+    .unwrap();
+
+    // the migrated plugin would be suspended by `after_execute` instead of rejecting
+    let response = simulate_execute(&app, &smart_account);
+    println!("response: {:?}", response);
+    assert!(response.valid);
+    assert!(response.plugins.is_empty());
+
+    // the simulation does not suspend it
+    let plugin: PluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account,
+            &QueryMsg::Plugin {
+                address: plugin_address.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(plugin.plugin.unwrap().status, PluginStatus::Active);
+}
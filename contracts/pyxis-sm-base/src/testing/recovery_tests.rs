@@ -1,22 +1,51 @@
 use std::vec;
 
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{Addr, Binary};
-use cw_multi_test::Executor;
-use pyxis_sm::msg::PyxisSudoMsg;
+use cosmwasm_std::{Addr, Binary, Deps, Env, StdError, StdResult};
+use cw_multi_test::{ContractWrapper, Executor};
+use pyxis_sm::msg::{PyxisSudoMsg, LEGACY_PLUGIN_INTERFACE_VERSION};
 use pyxis_sm::plugin_manager_msg::PluginType;
+use simple_recovery_plugin::contract::{
+    execute as recovery_plugin_execute, instantiate as recovery_plugin_instantiate,
+    query as recovery_plugin_query,
+};
+use simple_recovery_plugin::msg::{
+    ExecuteMsg as RecoveryPluginExecuteMsg, InstantiateMsg as RecoveryPluginInstantiateMsg,
+    QueryMsg as RecoveryPluginQueryMsg,
+};
 use simple_recovery_plugin::state::RecoveryConfig;
+use simple_recovery_plugin::ContractError as RecoveryPluginError;
 
 use crate::contract::instantiate;
-use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::error::ContractError;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, RecoveryLogResponse,
+    RecoveryStatusResponse,
+};
 use crate::testing::test_setup::{allow_plugin, mock_app, setup_contracts, SM_ADDRESS};
 
+// a recovery plugin of the first plugin interface does not answer the `InterfaceInfo` query
+fn legacy_recovery_plugin_query(
+    deps: Deps,
+    env: Env,
+    msg: RecoveryPluginQueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        RecoveryPluginQueryMsg::InterfaceInfo {} => {
+            Err(StdError::generic_err("unknown variant `interface_info`"))
+        }
+        msg => recovery_plugin_query(deps, env, msg),
+    }
+}
+
 #[test]
 fn proper_instantiation() {
     let mut deps = mock_dependencies();
     let info = mock_info(SM_ADDRESS, &[]);
     let msg = InstantiateMsg {
         plugin_manager_addr: Addr::unchecked("plugin_manager_addr"),
+        plugin_gas_budget: None,
+        initial_plugins: vec![],
     };
     let env = mock_env();
     let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
@@ -69,6 +98,8 @@ fn can_recover() {
         &ExecuteMsg::RegisterPlugin {
             plugin_address: contracts.get("recovery_plugin").unwrap().clone(),
             config: serde_json_wasm::to_string(&recovery_config).unwrap(),
+            filter: None,
+            scope: None,
         },
         &vec![],
     )
@@ -85,6 +116,15 @@ fn can_recover() {
     );
     assert!(response.is_err());
 
+    // a frozen smart account can still be recovered
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::Freeze {},
+        &vec![],
+    )
+    .unwrap();
+
     // call with correct caller should success
     let response = app.wasm_sudo(
         Addr::unchecked(SM_ADDRESS),
@@ -96,4 +136,389 @@ fn can_recover() {
     );
     println!("response: {:?}", response);
     assert!(response.is_ok());
+
+    // the recovery unfreezes the smart account
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("smart_account").unwrap().clone(),
+            &QueryMsg::Config {},
+        )
+        .unwrap();
+    assert!(!config.frozen);
+}
+
+#[test]
+fn recover_requires_threshold_approvals() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    // each recovery plugin trusts a different recoverer
+    for (plugin_name, recoverer) in [
+        ("recovery_plugin", "recoverer"),
+        ("recovery_plugin_2", "backup_recoverer"),
+    ] {
+        allow_plugin(
+            &mut app,
+            &contracts,
+            &code_ids,
+            plugin_name,
+            PluginType::Recovery,
+        );
+
+        let recovery_config = RecoveryConfig {
+            smart_account_address: contracts.get("smart_account").unwrap().clone(),
+            recover_address: Addr::unchecked(recoverer),
+        };
+
+        app.execute_contract(
+            Addr::unchecked(SM_ADDRESS),
+            contracts.get("smart_account").unwrap().clone(),
+            &ExecuteMsg::RegisterPlugin {
+                plugin_address: contracts.get(plugin_name).unwrap().clone(),
+                config: serde_json_wasm::to_string(&recovery_config).unwrap(),
+                filter: None,
+                scope: None,
+            },
+            &vec![],
+        )
+        .unwrap();
+    }
+
+    // threshold can not be higher than the number of recovery plugins
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::UpdateRecoveryPolicy { threshold: 3 },
+        &vec![],
+    );
+    assert!(response.is_err());
+
+    // require both recovery plugins to approve
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::UpdateRecoveryPolicy { threshold: 2 },
+        &vec![],
+    )
+    .unwrap();
+
+    // only one recovery plugin approves
+    let response = app.wasm_sudo(
+        Addr::unchecked(SM_ADDRESS),
+        &PyxisSudoMsg::Recover {
+            caller: "recoverer".to_string(),
+            pub_key: Binary::from([]),
+            credentials: Binary::from([]),
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_err());
+
+    // one approval is enough with a threshold of 1
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::UpdateRecoveryPolicy { threshold: 1 },
+        &vec![],
+    )
+    .unwrap();
+
+    let response = app.wasm_sudo(
+        Addr::unchecked(SM_ADDRESS),
+        &PyxisSudoMsg::Recover {
+            caller: "recoverer".to_string(),
+            pub_key: Binary::from([]),
+            credentials: Binary::from([]),
+        },
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+}
+
+#[test]
+fn recoveries_are_logged_with_a_nonce() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "recovery_plugin",
+        PluginType::Recovery,
+    );
+
+    let recovery_config = RecoveryConfig {
+        smart_account_address: contracts.get("smart_account").unwrap().clone(),
+        recover_address: Addr::unchecked("recoverer"),
+    };
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        contracts.get("smart_account").unwrap().clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("recovery_plugin").unwrap().clone(),
+            config: serde_json_wasm::to_string(&recovery_config).unwrap(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    // a failed recovery is not logged and does not increase the nonce
+    let response = app.wasm_sudo(
+        Addr::unchecked(SM_ADDRESS),
+        &PyxisSudoMsg::Recover {
+            caller: "incorrect_caller".to_string(),
+            pub_key: Binary::from([1]),
+            credentials: Binary::from([]),
+        },
+    );
+    assert!(response.is_err());
+
+    for pub_key in [[1u8], [2u8]] {
+        let response = app.wasm_sudo(
+            Addr::unchecked(SM_ADDRESS),
+            &PyxisSudoMsg::Recover {
+                caller: "recoverer".to_string(),
+                pub_key: Binary::from(pub_key),
+                credentials: Binary::from([]),
+            },
+        );
+        println!("response: {:?}", response);
+        assert!(response.is_ok());
+    }
+
+    let recovery_status: RecoveryStatusResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("smart_account").unwrap().clone(),
+            &QueryMsg::RecoveryStatus {},
+        )
+        .unwrap();
+    assert_eq!(recovery_status.nonce, 2);
+
+    let recovery_log: RecoveryLogResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("smart_account").unwrap().clone(),
+            &QueryMsg::RecoveryLog {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(recovery_log.records.len(), 2);
+    assert_eq!(recovery_log.records[0].nonce, 0);
+    assert_eq!(recovery_log.records[0].caller, "recoverer");
+    assert_eq!(
+        recovery_log.records[0].plugins,
+        vec![contracts.get("recovery_plugin").unwrap().clone()]
+    );
+    assert_ne!(
+        recovery_log.records[0].pub_key_hash,
+        recovery_log.records[1].pub_key_hash
+    );
+
+    let recovery_log: RecoveryLogResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contracts.get("smart_account").unwrap().clone(),
+            &QueryMsg::RecoveryLog {
+                start_after: Some(0),
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(recovery_log.records.len(), 1);
+    assert_eq!(recovery_log.records[0].nonce, 1);
+}
+
+#[test]
+fn recovery_plugin_rejects_a_used_nonce() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let recovery_plugin = contracts.get("recovery_plugin").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "recovery_plugin",
+        PluginType::Recovery,
+    );
+
+    let recovery_config = RecoveryConfig {
+        smart_account_address: smart_account.clone(),
+        recover_address: Addr::unchecked("recoverer"),
+    };
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: recovery_plugin.clone(),
+            config: serde_json_wasm::to_string(&recovery_config).unwrap(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    app.wasm_sudo(
+        Addr::unchecked(SM_ADDRESS),
+        &PyxisSudoMsg::Recover {
+            caller: "recoverer".to_string(),
+            pub_key: Binary::from([]),
+            credentials: Binary::from([]),
+        },
+    )
+    .unwrap();
+
+    // the approval of the first recovery can not be replayed
+    let response = app.execute_contract(
+        smart_account.clone(),
+        recovery_plugin.clone(),
+        &RecoveryPluginExecuteMsg::Recover {
+            caller: "recoverer".to_string(),
+            pub_key: Binary::from([]),
+            credentials: Binary::from([]),
+            nonce: 0,
+        },
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<RecoveryPluginError>(),
+        Some(RecoveryPluginError::NonceAlreadyUsed {
+            nonce: 0,
+            next_nonce: 1
+        })
+    ));
+
+    let response = app.execute_contract(
+        smart_account,
+        recovery_plugin,
+        &RecoveryPluginExecuteMsg::Recover {
+            caller: "recoverer".to_string(),
+            pub_key: Binary::from([]),
+            credentials: Binary::from([]),
+            nonce: 1,
+        },
+        &vec![],
+    );
+    assert!(response.is_ok());
+}
+
+#[test]
+fn recovery_plugin_rejects_an_invalid_config() {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let recovery_plugin = contracts.get("recovery_plugin").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "recovery_plugin",
+        PluginType::Recovery,
+    );
+
+    let recovery_config = RecoveryConfig {
+        smart_account_address: smart_account.clone(),
+        recover_address: Addr::unchecked("recoverer"),
+    };
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: recovery_plugin.clone(),
+            config: serde_json_wasm::to_string(&recovery_config).unwrap(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    // a malformed config is rejected instead of aborting the plugin
+    let response = app.execute_contract(
+        smart_account,
+        recovery_plugin,
+        &RecoveryPluginExecuteMsg::UpdateConfig {
+            config: "not a config".to_string(),
+        },
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<RecoveryPluginError>(),
+        Some(RecoveryPluginError::InvalidConfig { .. })
+    ));
+}
+
+#[test]
+fn legacy_recovery_plugin_can_not_be_registered() {
+    let (mut app, mut code_ids) = mock_app();
+
+    let mut contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let legacy_code_id = app.store_code(Box::new(ContractWrapper::new(
+        recovery_plugin_execute,
+        recovery_plugin_instantiate,
+        legacy_recovery_plugin_query,
+    )));
+    let legacy_plugin = app
+        .instantiate_contract(
+            legacy_code_id,
+            Addr::unchecked(SM_ADDRESS),
+            &RecoveryPluginInstantiateMsg {},
+            &vec![],
+            "legacy recovery plugin",
+            Some(contracts.get("plugin_manager").unwrap().to_string()),
+        )
+        .unwrap();
+    code_ids.insert("legacy_recovery_plugin", legacy_code_id);
+    contracts.insert("legacy_recovery_plugin".to_string(), legacy_plugin.clone());
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "legacy_recovery_plugin",
+        PluginType::Recovery,
+    );
+
+    let recovery_config = RecoveryConfig {
+        smart_account_address: smart_account.clone(),
+        recover_address: Addr::unchecked("recoverer"),
+    };
+
+    // the legacy plugin would not receive the recovery nonce
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account,
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: legacy_plugin,
+            config: serde_json_wasm::to_string(&recovery_config).unwrap(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::LegacyRecoveryPlugin {
+            version: LEGACY_PLUGIN_INTERFACE_VERSION
+        })
+    ));
 }
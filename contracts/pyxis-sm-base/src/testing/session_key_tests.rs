@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::vec;
+
+use cosmwasm_std::{coins, Addr, Binary, Uint64};
+use cw_multi_test::{App, Executor};
+use pyxis_sm::msg::{CallInfo, SdkMsg};
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, QueryMsg, SimulateExecuteResponse};
+use crate::testing::test_setup::{
+    authz_after_execute, bank_send, mock_app, setup_contracts, SM_ADDRESS,
+};
+
+const SESSION_KEY: &str = "session_key";
+
+// a smart account with a session key allowed to send 100 uaura,
+// authz transactions are restricted to session keys when `session_keys_only` is set
+fn setup_session_key(session_keys_only: bool) -> (App, HashMap<String, Addr>) {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let expiration = app.block_info().time.seconds() + 100;
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterSessionKey {
+            address: Addr::unchecked(SESSION_KEY),
+            expiration: Uint64::from(expiration),
+            type_urls: vec!["/cosmos.bank.v1beta1.MsgSend".to_string()],
+            contracts: vec![],
+            spend_limit: coins(100, "uaura"),
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    if session_keys_only {
+        app.execute_contract(
+            Addr::unchecked(SM_ADDRESS),
+            smart_account,
+            &ExecuteMsg::UpdateConfig {
+                plugin_manager_addr: None,
+                plugin_gas_budget: None,
+                session_keys_only: Some(true),
+            },
+            &vec![],
+        )
+        .unwrap();
+    }
+
+    (app, contracts)
+}
+
+fn simulate_authz_execute(
+    app: &App,
+    smart_account: &Addr,
+    fee_payer: &str,
+    msgs: Vec<SdkMsg>,
+) -> SimulateExecuteResponse {
+    app.wrap()
+        .query_wasm_smart(
+            smart_account.clone(),
+            &QueryMsg::SimulateExecute {
+                msgs,
+                call_info: CallInfo {
+                    fee_payer: fee_payer.to_string(),
+                    ..CallInfo::default()
+                },
+                is_authz: true,
+            },
+        )
+        .unwrap()
+}
+
+#[test]
+fn registering_session_key_does_not_restrict_authz() {
+    let (mut app, contracts) = setup_session_key(false);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.wasm_sudo(
+        smart_account,
+        &authz_after_execute("grantee", vec![bank_send("receiver", 10)]),
+    );
+    assert!(response.is_ok());
+}
+
+#[test]
+fn session_key_is_limited_to_its_scope_without_session_keys_only() {
+    let (mut app, contracts) = setup_session_key(false);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.wasm_sudo(
+        smart_account.clone(),
+        &authz_after_execute(SESSION_KEY, vec![bank_send("receiver", 60)]),
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    let response = app.wasm_sudo(
+        smart_account,
+        &authz_after_execute(SESSION_KEY, vec![bank_send("receiver", 60)]),
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::SessionKeySpendLimitExceeded { .. })
+    ));
+}
+
+#[test]
+fn other_grantees_are_rejected() {
+    let (mut app, contracts) = setup_session_key(true);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.wasm_sudo(
+        smart_account,
+        &authz_after_execute("grantee", vec![bank_send("receiver", 10)]),
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::NotSessionKey { .. })
+    ));
+}
+
+#[test]
+fn session_key_is_limited_to_its_spend_limit() {
+    let (mut app, contracts) = setup_session_key(true);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.wasm_sudo(
+        smart_account.clone(),
+        &authz_after_execute(SESSION_KEY, vec![bank_send("receiver", 60)]),
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    let response = app.wasm_sudo(
+        smart_account,
+        &authz_after_execute(SESSION_KEY, vec![bank_send("receiver", 60)]),
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::SessionKeySpendLimitExceeded { .. })
+    ));
+}
+
+#[test]
+fn session_key_is_limited_to_its_message_types() {
+    let (mut app, contracts) = setup_session_key(true);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = app.wasm_sudo(
+        smart_account,
+        &authz_after_execute(
+            SESSION_KEY,
+            vec![SdkMsg {
+                type_url: "/cosmos.staking.v1beta1.MsgUndelegate".to_string(),
+                value: Binary::from([]),
+            }],
+        ),
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::SessionKeyMessageNotAllowed { .. })
+    ));
+}
+
+#[test]
+fn session_key_expires() {
+    let (mut app, contracts) = setup_session_key(true);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(100));
+
+    let response = app.wasm_sudo(
+        smart_account,
+        &authz_after_execute(SESSION_KEY, vec![bank_send("receiver", 10)]),
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::SessionKeyExpired { .. })
+    ));
+}
+
+#[test]
+fn revoked_session_key_can_not_be_used() {
+    let (mut app, contracts) = setup_session_key(true);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RevokeAllSessionKeys {},
+        &vec![],
+    )
+    .unwrap();
+
+    // authz transactions stay restricted to session keys
+    let response = app.wasm_sudo(
+        smart_account,
+        &authz_after_execute(SESSION_KEY, vec![bank_send("receiver", 10)]),
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::NotSessionKey { .. })
+    ));
+}
+
+#[test]
+fn simulate_execute_checks_session_key_without_spending() {
+    let (mut app, contracts) = setup_session_key(true);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    let response = simulate_authz_execute(
+        &app,
+        &smart_account,
+        "grantee",
+        vec![bank_send("receiver", 10)],
+    );
+    assert!(!response.valid);
+    assert!(response.reason.unwrap().contains("Not a session key"));
+
+    let response = simulate_authz_execute(
+        &app,
+        &smart_account,
+        SESSION_KEY,
+        vec![bank_send("receiver", 120)],
+    );
+    assert!(!response.valid);
+    assert!(response.reason.unwrap().contains("spend limit exceeded"));
+
+    // simulations do not add to the spending of the session key
+    for _ in 0..2 {
+        let response = simulate_authz_execute(
+            &app,
+            &smart_account,
+            SESSION_KEY,
+            vec![bank_send("receiver", 60)],
+        );
+        assert!(response.valid);
+    }
+
+    let response = app.wasm_sudo(
+        smart_account,
+        &authz_after_execute(SESSION_KEY, vec![bank_send("receiver", 60)]),
+    );
+    assert!(response.is_ok());
+}
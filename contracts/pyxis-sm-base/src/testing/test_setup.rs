@@ -1,7 +1,16 @@
-use crate::contract::{execute, instantiate, query, sudo as sudo_fn};
+use crate::contract::{execute, instantiate, query, reply as reply_fn, sudo as sudo_fn};
 use crate::msg::InstantiateMsg;
-use cosmwasm_std::{Addr, Empty};
+use cosmos_sdk_proto::cosmos::authz::v1beta1::MsgExec;
+use cosmos_sdk_proto::cosmos::bank::v1beta1::MsgSend;
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin as ProtoCoin;
+use cosmos_sdk_proto::cosmwasm::wasm::v1::MsgExecuteContract;
+use cosmos_sdk_proto::traits::Message;
+use cosmos_sdk_proto::Any;
+use cosmwasm_std::{
+    coins, Addr, BankMsg, Binary, CosmosMsg, DepsMut, Empty, Env, Response, StdResult,
+};
 use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+use pyxis_sm::msg::{CallInfo, PyxisSudoMsg, SdkMsg};
 use pyxis_sm::plugin_manager_msg::PluginType;
 use sample_plugin::{
     contract::{
@@ -23,16 +32,26 @@ use simple_recovery_plugin::contract::{
 };
 use std::collections::HashMap;
 
-// since we haven't been able to use instantiate2 with cw_multi_test, we need to use a hardcoded address
+// the multi-test version of these tests has no instantiate2 and no bech32 addresses,
+// the smart account gets the address of its instantiation order,
+// creation at the instantiate2 address is tested with the factory in `pyxis-sm-factory`
 pub const SM_ADDRESS: &str = "contract1";
 
 pub fn smart_account_code() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(execute, instantiate, query).with_sudo(sudo_fn);
+    let contract = ContractWrapper::new(execute, instantiate, query)
+        .with_sudo(sudo_fn)
+        .with_reply(reply_fn);
     Box::new(contract)
 }
 
+// sample plugin can not be migrated, this lets tests migrate it to another code
+fn sample_plugin_migrate(_deps: DepsMut, _env: Env, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
 pub fn sample_plugin_code() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(plugin_execute, plugin_instantiate, plugin_query);
+    let contract = ContractWrapper::new(plugin_execute, plugin_instantiate, plugin_query)
+        .with_migrate(sample_plugin_migrate);
     Box::new(contract)
 }
 
@@ -63,9 +82,17 @@ pub fn mock_app<'a>() -> (App, HashMap<&'a str, u64>) {
 
     let sample_plugin_code = app.store_code(sample_plugin_code());
     code_ids.insert("sample_plugin", sample_plugin_code);
+    // plugins of `setup_contracts` are looked up by name in `allow_plugin`
+    for plugin_name in ["plugin_1", "plugin_2", "plugin_3", "plugin_4"] {
+        code_ids.insert(plugin_name, sample_plugin_code);
+    }
+
+    let sample_plugin_v2_code = app.store_code(sample_plugin_code());
+    code_ids.insert("sample_plugin_v2", sample_plugin_v2_code);
 
     let recovery_plugin_code = app.store_code(recovery_plugin_code());
     code_ids.insert("recovery_plugin", recovery_plugin_code);
+    code_ids.insert("recovery_plugin_2", recovery_plugin_code);
 
     let sample_plugin_manager_code = app.store_code(sample_plugin_manager_code());
     code_ids.insert("sample_plugin_manager", sample_plugin_manager_code);
@@ -96,7 +123,9 @@ pub fn setup_contracts<'a>(app: &mut App, code_ids: &HashMap<&str, u64>) -> Hash
         *code_ids.get("smart_account").unwrap(),
         Addr::unchecked(SM_ADDRESS),
         &InstantiateMsg {
-            plugin_manager_addr,
+            plugin_manager_addr: plugin_manager_addr.clone(),
+            plugin_gas_budget: None,
+            initial_plugins: vec![],
         },
         &vec![],
         "smart account 1",
@@ -114,7 +143,7 @@ pub fn setup_contracts<'a>(app: &mut App, code_ids: &HashMap<&str, u64>) -> Hash
             &PluginInstantiateMsg {},
             &vec![],
             "sample plugin 1",
-            Some(SM_ADDRESS.to_string()),
+            Some(plugin_manager_addr.to_string()),
         );
         println!("plugin_addr: {:?}", plugin_addr);
         assert!(plugin_addr.is_ok());
@@ -124,16 +153,18 @@ pub fn setup_contracts<'a>(app: &mut App, code_ids: &HashMap<&str, u64>) -> Hash
         contracts.insert(key, plugin_addr);
     }
 
-    // create a recovery plugin
-    let recovery_plugin_addr = app.instantiate_contract(
-        *code_ids.get("recovery_plugin").unwrap(),
-        Addr::unchecked(SM_ADDRESS),
-        &PluginInstantiateMsg {},
-        &vec![],
-        "recovery plugin",
-        Some(SM_ADDRESS.to_string()),
-    );
-    contracts.insert("recovery_plugin".to_string(), recovery_plugin_addr.unwrap());
+    // create 2 recovery plugins
+    for key in ["recovery_plugin", "recovery_plugin_2"] {
+        let recovery_plugin_addr = app.instantiate_contract(
+            *code_ids.get("recovery_plugin").unwrap(),
+            Addr::unchecked(SM_ADDRESS),
+            &PluginInstantiateMsg {},
+            &vec![],
+            "recovery plugin",
+            Some(plugin_manager_addr.to_string()),
+        );
+        contracts.insert(key.to_string(), recovery_plugin_addr.unwrap());
+    }
 
     contracts
 }
@@ -158,9 +189,98 @@ pub fn allow_plugin(
                 code_id: *code_ids.get(plugin_name).unwrap(),
                 version: "v0.1.0".to_string(),
                 enabled: true,
+                max_gas: 1_000_000,
             },
         },
         &vec![],
     )
     .unwrap();
 }
+
+// a `MsgSend` of `amount` uaura from the smart account
+pub fn bank_send(to_address: &str, amount: u128) -> SdkMsg {
+    SdkMsg {
+        type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+        value: Binary::from(
+            MsgSend {
+                from_address: SM_ADDRESS.to_string(),
+                to_address: to_address.to_string(),
+                amount: vec![ProtoCoin {
+                    denom: "uaura".to_string(),
+                    amount: amount.to_string(),
+                }],
+            }
+            .encode_to_vec(),
+        ),
+    }
+}
+
+// a bank send of `amount` uaura executed by the smart account itself
+pub fn bank_send_cosmos_msg(to_address: &str, amount: u128) -> CosmosMsg {
+    CosmosMsg::Bank(BankMsg::Send {
+        to_address: to_address.to_string(),
+        amount: coins(amount, "uaura"),
+    })
+}
+
+// a `MsgExecuteContract` sent by the smart account
+pub fn execute_contract(contract: &str, msg: Vec<u8>) -> SdkMsg {
+    SdkMsg {
+        type_url: "/cosmwasm.wasm.v1.MsgExecuteContract".to_string(),
+        value: Binary::from(
+            MsgExecuteContract {
+                sender: SM_ADDRESS.to_string(),
+                contract: contract.to_string(),
+                msg,
+                funds: vec![],
+            }
+            .encode_to_vec(),
+        ),
+    }
+}
+
+// wrap a `MsgExecuteContract` of the smart account in an authz `MsgExec`
+pub fn nested_execute_contract(contract: &str, msg: Vec<u8>) -> SdkMsg {
+    let msg_execute_contract = execute_contract(contract, msg);
+    SdkMsg {
+        type_url: "/cosmos.authz.v1beta1.MsgExec".to_string(),
+        value: Binary::from(
+            MsgExec {
+                grantee: SM_ADDRESS.to_string(),
+                msgs: vec![Any {
+                    type_url: msg_execute_contract.type_url,
+                    value: msg_execute_contract.value.to_vec(),
+                }],
+            }
+            .encode_to_vec(),
+        ),
+    }
+}
+
+pub fn pre_execute(msgs: Vec<SdkMsg>) -> PyxisSudoMsg {
+    PyxisSudoMsg::PreExecute {
+        msgs,
+        call_info: CallInfo::default(),
+        is_authz: false,
+    }
+}
+
+pub fn after_execute(msgs: Vec<SdkMsg>) -> PyxisSudoMsg {
+    PyxisSudoMsg::AfterExecute {
+        msgs,
+        call_info: CallInfo::default(),
+        is_authz: false,
+    }
+}
+
+// an authz transaction paid by `fee_payer`
+pub fn authz_after_execute(fee_payer: &str, msgs: Vec<SdkMsg>) -> PyxisSudoMsg {
+    PyxisSudoMsg::AfterExecute {
+        msgs,
+        call_info: CallInfo {
+            fee_payer: fee_payer.to_string(),
+            ..CallInfo::default()
+        },
+        is_authz: true,
+    }
+}
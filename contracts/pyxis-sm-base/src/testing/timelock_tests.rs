@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::vec;
+
+use cosmwasm_std::{to_json_vec, Addr};
+use cw_multi_test::{App, Executor};
+use pyxis_sm::msg::SdkMsg;
+use pyxis_sm::plugin_manager_msg::PluginType;
+use sample_plugin::ContractError as SamplePluginError;
+
+use crate::error::ContractError;
+use crate::msg::{
+    AllPluginsResponse, ConfigResponse, ExecuteMsg, PendingActionsResponse, PluginResponse,
+    QueryMsg,
+};
+use crate::state::SensitiveAction;
+use crate::testing::test_setup::{
+    after_execute, allow_plugin, bank_send, execute_contract, mock_app, pre_execute,
+    setup_contracts, SM_ADDRESS,
+};
+
+const GUARDIAN: &str = "guardian";
+const TIMELOCK: u64 = 86400;
+
+// a smart account with plugin_1 registered with `plugin_config`, a guardian and a timelock
+fn setup_timelocked_account(plugin_config: &str) -> (App, HashMap<String, Addr>) {
+    let (mut app, code_ids) = mock_app();
+
+    let contracts = setup_contracts(&mut app, &code_ids);
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    allow_plugin(
+        &mut app,
+        &contracts,
+        &code_ids,
+        "plugin_1",
+        PluginType::Other,
+    );
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::RegisterPlugin {
+            plugin_address: contracts.get("plugin_1").unwrap().clone(),
+            config: plugin_config.to_string(),
+            filter: None,
+            scope: None,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::UpdateGuardians {
+            guardians: vec![Addr::unchecked(GUARDIAN)],
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    // setting the timelock applies immediately
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account,
+        &ExecuteMsg::UpdateTimelock { timelock: TIMELOCK },
+        &vec![],
+    )
+    .unwrap();
+
+    (app, contracts)
+}
+
+fn pending_actions(app: &App, smart_account: &Addr) -> PendingActionsResponse {
+    app.wrap()
+        .query_wasm_smart(
+            smart_account.clone(),
+            &QueryMsg::PendingActions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+}
+
+fn all_plugins(app: &App, smart_account: &Addr) -> AllPluginsResponse {
+    app.wrap()
+        .query_wasm_smart(
+            smart_account.clone(),
+            &QueryMsg::AllPlugins {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+}
+
+fn config(app: &App, smart_account: &Addr) -> ConfigResponse {
+    app.wrap()
+        .query_wasm_smart(smart_account.clone(), &QueryMsg::Config {})
+        .unwrap()
+}
+
+fn plugin_config(app: &App, smart_account: &Addr, plugin_address: &Addr) -> String {
+    let plugin: PluginResponse = app
+        .wrap()
+        .query_wasm_smart(
+            smart_account.clone(),
+            &QueryMsg::Plugin {
+                address: plugin_address.to_string(),
+            },
+        )
+        .unwrap();
+    plugin.plugin.unwrap().config
+}
+
+// an `ExecuteMsg` sent by the smart account to itself
+fn execute_self(msg: &ExecuteMsg) -> SdkMsg {
+    execute_contract(SM_ADDRESS, to_json_vec(msg).unwrap())
+}
+
+#[test]
+fn setting_timelock_applies_immediately() {
+    let (app, contracts) = setup_timelocked_account("approve");
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    assert_eq!(config(&app, &smart_account).timelock, TIMELOCK);
+    assert!(pending_actions(&app, &smart_account).actions.is_empty());
+}
+
+#[test]
+fn unregister_plugin_waits_for_timelock() {
+    let (mut app, contracts) = setup_timelocked_account("approve");
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::UnregisterPlugin {
+            plugin_address: plugin_address.clone(),
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    // unregistering the plugin is queued
+    assert_eq!(all_plugins(&app, &smart_account).plugins.len(), 1);
+    let pending = pending_actions(&app, &smart_account);
+    assert_eq!(pending.actions.len(), 1);
+    assert_eq!(
+        pending.actions[0].action,
+        SensitiveAction::UnregisterPlugin { plugin_address }
+    );
+    let id = pending.actions[0].id;
+
+    let response = app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::ExecutePendingAction { id },
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::PendingActionLocked { .. })
+    ));
+
+    app.update_block(|block| block.time = block.time.plus_seconds(TIMELOCK));
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::ExecutePendingAction { id },
+        &vec![],
+    )
+    .unwrap();
+
+    assert!(all_plugins(&app, &smart_account).plugins.is_empty());
+    assert!(pending_actions(&app, &smart_account).actions.is_empty());
+}
+
+#[test]
+fn guardian_cancels_lowering_timelock() {
+    let (mut app, contracts) = setup_timelocked_account("approve");
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    // lowering the timelock is queued
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::UpdateTimelock { timelock: 0 },
+        &vec![],
+    )
+    .unwrap();
+
+    let pending = pending_actions(&app, &smart_account);
+    assert_eq!(pending.actions.len(), 1);
+    assert_eq!(
+        pending.actions[0].action,
+        SensitiveAction::UpdateTimelock { timelock: 0 }
+    );
+
+    app.execute_contract(
+        Addr::unchecked(GUARDIAN),
+        smart_account.clone(),
+        &ExecuteMsg::CancelPendingAction {
+            id: pending.actions[0].id,
+        },
+        &vec![],
+    )
+    .unwrap();
+
+    assert!(pending_actions(&app, &smart_account).actions.is_empty());
+    assert_eq!(config(&app, &smart_account).timelock, TIMELOCK);
+}
+
+#[test]
+fn stranger_cannot_cancel_pending_action() {
+    let (mut app, contracts) = setup_timelocked_account("approve");
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::UpdateTimelock { timelock: 0 },
+        &vec![],
+    )
+    .unwrap();
+    let id = pending_actions(&app, &smart_account).actions[0].id;
+
+    let response = app.execute_contract(
+        Addr::unchecked("stranger"),
+        smart_account.clone(),
+        &ExecuteMsg::CancelPendingAction { id },
+        &vec![],
+    );
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::Unauthorized {})
+    ));
+    assert_eq!(pending_actions(&app, &smart_account).actions.len(), 1);
+}
+
+#[test]
+fn queued_unregister_does_not_skip_plugin_hooks() {
+    let (mut app, contracts) = setup_timelocked_account("reject");
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    // the rejecting plugin is only unregistered once the timelock has passed,
+    // it still checks the funds sent in the same transaction
+    let msgs = vec![
+        execute_self(&ExecuteMsg::UnregisterPlugin { plugin_address }),
+        bank_send("receiver", 10),
+    ];
+    for sudo_msg in [pre_execute(msgs.clone()), after_execute(msgs)] {
+        let response = app.wasm_sudo(smart_account.clone(), &sudo_msg);
+        println!("response: {:?}", response);
+        assert!(matches!(
+            response.unwrap_err().downcast_ref::<SamplePluginError>(),
+            Some(SamplePluginError::Rejected { .. })
+        ));
+    }
+}
+
+#[test]
+fn executed_unregister_skips_plugin_hooks() {
+    let (mut app, contracts) = setup_timelocked_account("reject");
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::UnregisterPlugin { plugin_address },
+        &vec![],
+    )
+    .unwrap();
+    let id = pending_actions(&app, &smart_account).actions[0].id;
+
+    app.update_block(|block| block.time = block.time.plus_seconds(TIMELOCK));
+
+    // the plugin is unregistered by the transaction, it is not called anymore
+    let response = app.wasm_sudo(
+        smart_account,
+        &pre_execute(vec![
+            execute_self(&ExecuteMsg::ExecutePendingAction { id }),
+            bank_send("receiver", 10),
+        ]),
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+}
+
+#[test]
+fn account_updates_wait_for_timelock() {
+    let (mut app, contracts) = setup_timelocked_account("approve");
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+    let plugin_address = contracts.get("plugin_1").unwrap().clone();
+
+    for msg in [
+        ExecuteMsg::UpdatePluginConfig {
+            plugin_address: plugin_address.clone(),
+            config: "reject".to_string(),
+        },
+        ExecuteMsg::UpdateGuardians {
+            guardians: vec![Addr::unchecked("new_guardian")],
+        },
+        ExecuteMsg::UpdateRecoveryPolicy { threshold: 1 },
+    ] {
+        app.execute_contract(
+            Addr::unchecked(SM_ADDRESS),
+            smart_account.clone(),
+            &msg,
+            &vec![],
+        )
+        .unwrap();
+    }
+
+    // nothing is applied before the timelock has passed
+    assert_eq!(
+        plugin_config(&app, &smart_account, &plugin_address),
+        "approve"
+    );
+    assert_eq!(
+        config(&app, &smart_account).guardians,
+        vec![Addr::unchecked(GUARDIAN)]
+    );
+
+    let pending = pending_actions(&app, &smart_account);
+    assert_eq!(
+        pending
+            .actions
+            .iter()
+            .map(|pending_action| pending_action.action.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            SensitiveAction::UpdatePluginConfig {
+                plugin_address: plugin_address.clone(),
+                config: "reject".to_string(),
+            },
+            SensitiveAction::UpdateGuardians {
+                guardians: vec![Addr::unchecked("new_guardian")],
+            },
+            SensitiveAction::UpdateRecoveryPolicy { threshold: 1 },
+        ]
+    );
+
+    app.update_block(|block| block.time = block.time.plus_seconds(TIMELOCK));
+
+    for pending_action in &pending.actions[..2] {
+        app.execute_contract(
+            Addr::unchecked(SM_ADDRESS),
+            smart_account.clone(),
+            &ExecuteMsg::ExecutePendingAction {
+                id: pending_action.id,
+            },
+            &vec![],
+        )
+        .unwrap();
+    }
+
+    assert_eq!(
+        plugin_config(&app, &smart_account, &plugin_address),
+        "reject"
+    );
+    assert_eq!(
+        config(&app, &smart_account).guardians,
+        vec![Addr::unchecked("new_guardian")]
+    );
+}
+
+#[test]
+fn unfreezing_itself_waits_for_timelock() {
+    let (mut app, contracts) = setup_timelocked_account("approve");
+    let smart_account = contracts.get("smart_account").unwrap().clone();
+
+    app.execute_contract(
+        Addr::unchecked(GUARDIAN),
+        smart_account.clone(),
+        &ExecuteMsg::Freeze {},
+        &vec![],
+    )
+    .unwrap();
+
+    // unfreezing itself is queued, the guardian can cancel it
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::Unfreeze {},
+        &vec![],
+    )
+    .unwrap();
+    assert!(config(&app, &smart_account).frozen);
+    let pending = pending_actions(&app, &smart_account);
+    assert_eq!(pending.actions[0].action, SensitiveAction::Unfreeze {});
+    let id = pending.actions[0].id;
+
+    app.update_block(|block| block.time = block.time.plus_seconds(TIMELOCK));
+
+    // the frozen smart account can execute its pending unfreeze
+    let response = app.wasm_sudo(
+        smart_account.clone(),
+        &after_execute(vec![execute_self(&ExecuteMsg::ExecutePendingAction { id })]),
+    );
+    println!("response: {:?}", response);
+    assert!(response.is_ok());
+
+    app.execute_contract(
+        Addr::unchecked(SM_ADDRESS),
+        smart_account.clone(),
+        &ExecuteMsg::ExecutePendingAction { id },
+        &vec![],
+    )
+    .unwrap();
+    assert!(!config(&app, &smart_account).frozen);
+}
@@ -0,0 +1,12 @@
+use cosmwasm_schema::write_api;
+
+use pyxis_sm_factory::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+fn main() {
+    write_api! {
+        instantiate: InstantiateMsg,
+        migrate: MigrateMsg,
+        execute: ExecuteMsg,
+        query: QueryMsg,
+    }
+}
@@ -0,0 +1,218 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    instantiate2_address, to_json_binary, to_json_vec, Addr, Binary, Deps, DepsMut, Env,
+    MessageInfo, Order, Response, StdResult, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw_ownable::{assert_owner, get_ownership, update_ownership};
+use cw_storage_plus::Bound;
+use sha2::{Digest, Sha256};
+
+use crate::error::ContractError;
+use crate::msg::{
+    AccountResponse, AccountsResponse, AllowedCodeIdsResponse, ExecuteMsg, InstantiateMsg,
+    MigrateMsg, PredictAddressResponse, QueryMsg,
+};
+use crate::state::{AccountRecord, ACCOUNTS, ALLOWED_CODE_IDS};
+use pyxis_sm_base::msg::InstantiateMsg as AccountInstantiateMsg;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:pyxis-sm-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// settings for query pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Handling contract instantiation
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    cw_ownable::initialize_owner(deps.storage, deps.api, Some(&msg.owner))?;
+
+    for code_id in msg.allowed_code_ids {
+        ALLOWED_CODE_IDS.save(deps.storage, code_id, &true)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("owner", info.sender))
+}
+
+/// Handling contract migration
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    match msg {}
+}
+
+/// Handling contract execution
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateAccount {
+            code_id,
+            pubkey,
+            salt,
+            msg,
+        } => create_account(deps, env, info, code_id, pubkey, salt, msg),
+        ExecuteMsg::AllowCodeId { code_id } => {
+            assert_owner(deps.storage, &info.sender).map_err(|_| ContractError::Unauthorized {})?;
+
+            ALLOWED_CODE_IDS.save(deps.storage, code_id, &true)?;
+            Ok(Response::new()
+                .add_attribute("action", "allow_code_id")
+                .add_attribute("code_id", code_id.to_string()))
+        }
+        ExecuteMsg::DisallowCodeId { code_id } => {
+            assert_owner(deps.storage, &info.sender).map_err(|_| ContractError::Unauthorized {})?;
+
+            ALLOWED_CODE_IDS.remove(deps.storage, code_id);
+            Ok(Response::new()
+                .add_attribute("action", "disallow_code_id")
+                .add_attribute("code_id", code_id.to_string()))
+        }
+        ExecuteMsg::UpdateOwnership(action) => {
+            update_ownership(deps, &env.block, &info.sender, action)?;
+
+            Ok(Response::new().add_attribute("action", "update_ownership"))
+        }
+    }
+}
+
+/// Instantiate a smart account at the address derived from its public key
+/// the smart account is its own admin
+fn create_account(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    code_id: u64,
+    pubkey: Binary,
+    salt: Binary,
+    msg: AccountInstantiateMsg,
+) -> Result<Response, ContractError> {
+    if !ALLOWED_CODE_IDS.has(deps.storage, code_id) {
+        return Err(ContractError::CodeIdNotAllowed { code_id });
+    }
+
+    let salt = account_salt(&pubkey, &salt, &msg)?;
+    let address = predict_address(deps.as_ref(), &env, code_id, &salt)?;
+    if ACCOUNTS.has(deps.storage, &address) {
+        return Err(ContractError::AccountAlreadyCreated {
+            address: address.to_string(),
+        });
+    }
+
+    ACCOUNTS.save(
+        deps.storage,
+        &address,
+        &AccountRecord {
+            address: address.clone(),
+            creator: info.sender.clone(),
+            code_id,
+            pubkey,
+            height: env.block.height,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_account")
+        .add_attribute("address", address.to_string())
+        .add_attribute("creator", info.sender)
+        .add_attribute("code_id", code_id.to_string())
+        .add_message(WasmMsg::Instantiate2 {
+            admin: Some(address.to_string()),
+            code_id,
+            label: format!("pyxis smart account {}", address),
+            msg: to_json_binary(&msg)?,
+            funds: info.funds,
+            salt,
+        }))
+}
+
+/// the instantiate2 salt of a smart account, derived from its public key, the given salt
+/// and its instantiate message, so that the address can not be taken with another message
+/// each part is prefixed with its length, another split of the same bytes gives another salt
+fn account_salt(pubkey: &Binary, salt: &Binary, msg: &AccountInstantiateMsg) -> StdResult<Binary> {
+    let mut hasher = Sha256::new();
+    for part in [
+        pubkey.as_slice(),
+        salt.as_slice(),
+        to_json_vec(msg)?.as_slice(),
+    ] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    Ok(Binary::from(hasher.finalize().to_vec()))
+}
+
+/// the address of a smart account instantiated by this factory with `Instantiate2`
+fn predict_address(
+    deps: Deps,
+    env: &Env,
+    code_id: u64,
+    salt: &Binary,
+) -> Result<Addr, ContractError> {
+    let checksum = deps.querier.query_wasm_code_info(code_id)?.checksum;
+    let creator = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+
+    let address = instantiate2_address(checksum.as_slice(), &creator, salt.as_slice())
+        .map_err(ContractError::Instantiate2Address)?;
+
+    Ok(deps.api.addr_humanize(&address)?)
+}
+
+/// Handling contract query
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let response = match msg {
+        QueryMsg::PredictAddress {
+            code_id,
+            pubkey,
+            salt,
+            msg,
+        } => {
+            let salt = account_salt(&pubkey, &salt, &msg)?;
+            let address = predict_address(deps, &env, code_id, &salt)?;
+            to_json_binary(&PredictAddressResponse { address })
+        }
+        QueryMsg::Account { address } => {
+            let address = deps.api.addr_validate(&address)?;
+            let account = ACCOUNTS.may_load(deps.storage, &address)?;
+            to_json_binary(&AccountResponse { account })
+        }
+        QueryMsg::Accounts { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+
+            let accounts = ACCOUNTS
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, account)| account))
+                .collect::<StdResult<_>>()?;
+
+            to_json_binary(&AccountsResponse { accounts })
+        }
+        QueryMsg::AllowedCodeIds {} => {
+            let code_ids = ALLOWED_CODE_IDS
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<_>>()?;
+
+            to_json_binary(&AllowedCodeIdsResponse { code_ids })
+        }
+        QueryMsg::Ownership {} => to_json_binary(&get_ownership(deps.storage)?),
+    }?;
+
+    Ok(response)
+}
@@ -0,0 +1,24 @@
+use cosmwasm_std::{Instantiate2AddressError, StdError};
+use cw_ownable::OwnershipError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("Smart account address can not be derived: {0}")]
+    Instantiate2Address(Instantiate2AddressError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Smart account code_id {code_id} is not allowed")]
+    CodeIdNotAllowed { code_id: u64 },
+
+    #[error("Smart account {address} has already been created")]
+    AccountAlreadyCreated { address: String },
+}
@@ -0,0 +1,85 @@
+use crate::state::AccountRecord;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary};
+use cw_ownable::{cw_ownable_execute, cw_ownable_query};
+use pyxis_sm_base::msg::InstantiateMsg as AccountInstantiateMsg;
+
+/// Message type for `instantiate` entry_point
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub owner: String,
+    // smart account code_ids this factory can deploy
+    pub allowed_code_ids: Vec<u64>,
+}
+
+/// Message type for `execute` entry_point
+#[cw_ownable_execute]
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Instantiate a smart account with `Instantiate2`, its address is given by `PredictAddress`
+    /// the address depends on the instantiate message, it can not be taken with another message
+    /// the funds are sent to the new smart account
+    CreateAccount {
+        code_id: u64,
+        pubkey: Binary,
+        // lets a public key own several smart accounts
+        salt: Binary,
+        msg: AccountInstantiateMsg,
+    },
+    /// Allow this factory to deploy a smart account code, called by the owner
+    AllowCodeId { code_id: u64 },
+    /// Stop this factory from deploying a smart account code, called by the owner
+    DisallowCodeId { code_id: u64 },
+}
+
+/// Message type for `migrate` entry_point
+#[cw_serde]
+pub enum MigrateMsg {}
+
+/// Message type for `query` entry_point
+#[cw_ownable_query]
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Address of the smart account created by `CreateAccount` with the same parameters
+    #[returns(PredictAddressResponse)]
+    PredictAddress {
+        code_id: u64,
+        pubkey: Binary,
+        salt: Binary,
+        msg: AccountInstantiateMsg,
+    },
+
+    #[returns(AccountResponse)]
+    Account { address: String },
+
+    #[returns(AccountsResponse)]
+    Accounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(AllowedCodeIdsResponse)]
+    AllowedCodeIds {},
+}
+
+#[cw_serde]
+pub struct PredictAddressResponse {
+    pub address: Addr,
+}
+
+#[cw_serde]
+pub struct AccountResponse {
+    // None if the smart account was not created by this factory
+    pub account: Option<AccountRecord>,
+}
+
+#[cw_serde]
+pub struct AccountsResponse {
+    pub accounts: Vec<AccountRecord>,
+}
+
+#[cw_serde]
+pub struct AllowedCodeIdsResponse {
+    pub code_ids: Vec<u64>,
+}
@@ -0,0 +1,20 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary};
+use cw_storage_plus::Map;
+
+/// AccountRecord is the creation record of a smart account created by this factory
+#[cw_serde]
+pub struct AccountRecord {
+    pub address: Addr,
+    // sender of the `CreateAccount` message
+    pub creator: Addr,
+    pub code_id: u64,
+    pub pubkey: Binary,
+    // block height of the creation
+    pub height: u64,
+}
+
+// ALLOWED_CODE_IDS is the set of smart account code_ids this factory can deploy
+pub const ALLOWED_CODE_IDS: Map<u64, bool> = Map::new("allowed_code_ids");
+// ACCOUNTS is a map of smart account address to AccountRecord
+pub const ACCOUNTS: Map<&Addr, AccountRecord> = Map::new("accounts");
@@ -0,0 +1,161 @@
+use cosmwasm_std::{Addr, Binary};
+use cw_multi_test::Executor;
+use pyxis_sm_base::msg::{ConfigResponse, QueryMsg as AccountQueryMsg};
+
+use crate::error::ContractError;
+use crate::msg::{AccountResponse, ExecuteMsg, PredictAddressResponse, QueryMsg};
+use crate::testing::test_setup::{
+    account_instantiate_msg, mock_app, pubkey, setup_factory, FactoryApp, CREATOR,
+};
+
+fn predict_address(app: &FactoryApp, factory: &Addr, code_id: u64, salt: Binary) -> Addr {
+    let response: PredictAddressResponse = app
+        .wrap()
+        .query_wasm_smart(
+            factory.clone(),
+            &QueryMsg::PredictAddress {
+                code_id,
+                pubkey: pubkey(),
+                salt,
+                msg: account_instantiate_msg(app),
+            },
+        )
+        .unwrap();
+    response.address
+}
+
+fn create_account(
+    app: &mut FactoryApp,
+    factory: &Addr,
+    code_id: u64,
+    salt: Binary,
+) -> anyhow::Result<Addr> {
+    let creator = app.api().addr_make(CREATOR);
+    let msg = account_instantiate_msg(app);
+    let response = app.execute_contract(
+        creator,
+        factory.clone(),
+        &ExecuteMsg::CreateAccount {
+            code_id,
+            pubkey: pubkey(),
+            salt,
+            msg,
+        },
+        &[],
+    )?;
+
+    // the address given by multi-test to the instantiated contract
+    let address = response
+        .events
+        .iter()
+        .filter(|event| event.ty == "instantiate")
+        .flat_map(|event| event.attributes.iter())
+        .find(|attribute| attribute.key == "_contract_address")
+        .map(|attribute| Addr::unchecked(attribute.value.clone()))
+        .unwrap();
+    Ok(address)
+}
+
+#[test]
+fn account_is_created_at_the_predicted_address() {
+    let mut app = mock_app();
+    let (factory, code_id) = setup_factory(&mut app);
+
+    let predicted = predict_address(&app, &factory, code_id, Binary::default());
+    let address = create_account(&mut app, &factory, code_id, Binary::default()).unwrap();
+    assert_eq!(address, predicted);
+
+    // the created contract is a smart account
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(address.clone(), &AccountQueryMsg::Config {})
+        .unwrap();
+    assert_eq!(
+        config.plugin_manager_addr,
+        account_instantiate_msg(&app).plugin_manager_addr
+    );
+
+    let account: AccountResponse = app
+        .wrap()
+        .query_wasm_smart(
+            factory,
+            &QueryMsg::Account {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    let account = account.account.unwrap();
+    assert_eq!(account.creator, app.api().addr_make(CREATOR));
+    assert_eq!(account.code_id, code_id);
+}
+
+#[test]
+fn account_address_depends_on_the_instantiate_message() {
+    let mut app = mock_app();
+    let (factory, code_id) = setup_factory(&mut app);
+
+    let predicted = predict_address(&app, &factory, code_id, Binary::default());
+
+    // another message with the same public key and salt can not take the address
+    let response: PredictAddressResponse = app
+        .wrap()
+        .query_wasm_smart(
+            factory,
+            &QueryMsg::PredictAddress {
+                code_id,
+                pubkey: pubkey(),
+                salt: Binary::default(),
+                msg: pyxis_sm_base::msg::InstantiateMsg {
+                    plugin_manager_addr: app.api().addr_make("attacker"),
+                    plugin_gas_budget: None,
+                    initial_plugins: vec![],
+                },
+            },
+        )
+        .unwrap();
+    assert_ne!(response.address, predicted);
+}
+
+#[test]
+fn account_address_depends_on_the_pubkey_and_salt_split() {
+    let mut app = mock_app();
+    let (factory, code_id) = setup_factory(&mut app);
+
+    // the same bytes split differently between the public key and the salt
+    let predict = |pubkey: &[u8], salt: &[u8]| -> Addr {
+        let response: PredictAddressResponse = app
+            .wrap()
+            .query_wasm_smart(
+                factory.clone(),
+                &QueryMsg::PredictAddress {
+                    code_id,
+                    pubkey: Binary::from(pubkey),
+                    salt: Binary::from(salt),
+                    msg: account_instantiate_msg(&app),
+                },
+            )
+            .unwrap();
+        response.address
+    };
+    assert_ne!(predict(b"pubkey", b"1"), predict(b"pubkey1", b""));
+    assert_ne!(predict(b"pubkey", b"1"), predict(b"pubke", b"y1"));
+}
+
+#[test]
+fn account_is_created_once() {
+    let mut app = mock_app();
+    let (factory, code_id) = setup_factory(&mut app);
+
+    create_account(&mut app, &factory, code_id, Binary::default()).unwrap();
+
+    let response = create_account(&mut app, &factory, code_id, Binary::default());
+    assert!(matches!(
+        response.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::AccountAlreadyCreated { .. })
+    ));
+
+    // another salt gives another smart account
+    let predicted = predict_address(&app, &factory, code_id, Binary::from(b"1".as_slice()));
+    let address = create_account(&mut app, &factory, code_id, Binary::from(b"1".as_slice()));
+    assert_eq!(address.unwrap(), predicted);
+}
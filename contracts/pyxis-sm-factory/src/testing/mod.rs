@@ -0,0 +1,2 @@
+pub mod factory_tests;
+pub mod test_setup;
@@ -0,0 +1,77 @@
+use cosmwasm_std::{Addr, Binary, Empty};
+use cw_multi_test::addons::{MockAddressGenerator, MockApiBech32};
+use cw_multi_test::{no_init, App, AppBuilder, Contract, ContractWrapper, Executor, WasmKeeper};
+use pyxis_sm_base::contract::{
+    execute as account_execute, instantiate as account_instantiate, query as account_query,
+    reply as account_reply, sudo as account_sudo,
+};
+use pyxis_sm_base::msg::InstantiateMsg as AccountInstantiateMsg;
+
+use crate::contract::{execute, instantiate, query};
+use crate::msg::InstantiateMsg;
+
+pub type FactoryApp = App<
+    cw_multi_test::BankKeeper,
+    MockApiBech32,
+    cosmwasm_std::testing::MockStorage,
+    cw_multi_test::FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+>;
+
+pub const OWNER: &str = "owner";
+pub const CREATOR: &str = "creator";
+
+pub fn factory_code() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+pub fn smart_account_code() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(account_execute, account_instantiate, account_query)
+        .with_sudo(account_sudo)
+        .with_reply(account_reply);
+    Box::new(contract)
+}
+
+// instantiate2 addresses are only supported by multi-test with bech32 addresses,
+// every address of the tests is made with `addr_make`
+pub fn mock_app() -> FactoryApp {
+    AppBuilder::default()
+        .with_api(MockApiBech32::new("aura"))
+        .with_wasm(WasmKeeper::default().with_address_generator(MockAddressGenerator))
+        .build(no_init)
+}
+
+// a factory allowed to deploy the smart account code, return its address and the code_id
+pub fn setup_factory(app: &mut FactoryApp) -> (Addr, u64) {
+    let smart_account_code_id = app.store_code(smart_account_code());
+    let factory_code_id = app.store_code(factory_code());
+
+    let owner = app.api().addr_make(OWNER);
+    let factory = app
+        .instantiate_contract(
+            factory_code_id,
+            owner.clone(),
+            &InstantiateMsg {
+                owner: owner.to_string(),
+                allowed_code_ids: vec![smart_account_code_id],
+            },
+            &[],
+            "pyxis smart account factory",
+            None,
+        )
+        .unwrap();
+
+    (factory, smart_account_code_id)
+}
+
+pub fn account_instantiate_msg(app: &FactoryApp) -> AccountInstantiateMsg {
+    AccountInstantiateMsg {
+        plugin_manager_addr: app.api().addr_make("plugin_manager"),
+        plugin_gas_budget: None,
+        initial_plugins: vec![],
+    }
+}
+
+pub fn pubkey() -> Binary {
+    Binary::from(b"pubkey".as_slice())
+}
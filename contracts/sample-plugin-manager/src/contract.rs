@@ -1,7 +1,7 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, ContractInfoResponse, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
+    to_json_binary, Addr, Binary, ContractInfoResponse, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
     Order, QueryRequest, Reply, Response, StdError, StdResult, WasmMsg, WasmQuery,
 };
 use cw2::set_contract_version;
@@ -9,9 +9,15 @@ use cw_ownable::{assert_owner, update_ownership};
 use cw_storage_plus::Bound;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg};
-use crate::state::{Plugin, PLUGINS};
-use pyxis_sm::plugin_manager_msg::{AllPluginsResponse, PluginResponse, QueryMsg};
+use crate::msg::{
+    AccountConfigResponse, AccountPluginResponse, AccountQueryMsg, ExecuteMsg, InstantiateMsg,
+    MigrateMsg,
+};
+use crate::state::{Plugin, PAUSED, PLUGINS, PLUGIN_ACCOUNTS, PLUGIN_TEMPLATES, PLUGIN_USAGE};
+use pyxis_sm::plugin_manager_msg::{
+    AllPluginsResponse, DisabledPluginsResponse, PluginAccountsResponse, PluginResponse,
+    PluginTemplateResponse, QueryMsg,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:sample-plugin-manager";
@@ -138,9 +144,164 @@ pub fn execute(
 
             Ok(Response::new().add_attribute("action", "update_ownership"))
         }
+        ExecuteMsg::AllowPluginTemplate { template } => {
+            assert_owner(deps.storage, &info.sender).map_err(|_| ContractError::Unauthorized {})?;
+
+            // smart accounts need a gas limit to call the instances
+            if template.max_gas == 0 {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "Invalid plugin max_gas",
+                )));
+            }
+
+            PLUGIN_TEMPLATES.save(deps.storage, template.code_id, &template)?;
+            Ok(Response::new().add_attributes(vec![
+                ("action", "allow_plugin_template"),
+                ("code_id", template.code_id.to_string().as_str()),
+            ]))
+        }
+        ExecuteMsg::DisallowPluginTemplate { code_id } => {
+            assert_owner(deps.storage, &info.sender).map_err(|_| ContractError::Unauthorized {})?;
+
+            // instances already allowed are kept, they can be disabled with `UpdatePlugin`
+            PLUGIN_TEMPLATES.remove(deps.storage, code_id);
+            Ok(Response::new().add_attributes(vec![
+                ("action", "disallow_plugin_template"),
+                ("code_id", code_id.to_string().as_str()),
+            ]))
+        }
+        ExecuteMsg::PluginInstantiated { plugin_address } => {
+            let plugin_address = deps.api.addr_validate(&plugin_address)?;
+            if PLUGINS.has(deps.storage, plugin_address.as_str()) {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "Plugin is already allowed",
+                )));
+            }
+
+            let contract_info: ContractInfoResponse =
+                deps.querier
+                    .query(&QueryRequest::Wasm(WasmQuery::ContractInfo {
+                        contract_addr: plugin_address.to_string(),
+                    }))?;
+
+            // only the smart account which instantiated the instance can report it
+            if contract_info.creator != info.sender {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let template = PLUGIN_TEMPLATES
+                .may_load(deps.storage, contract_info.code_id)?
+                .filter(|template| template.enabled)
+                .ok_or_else(|| {
+                    ContractError::Std(StdError::generic_err("Plugin template not found"))
+                })?;
+
+            let plugin_info = Plugin {
+                name: template.name,
+                plugin_type: template.plugin_type,
+                code_id: template.code_id,
+                version: template.version,
+                address: plugin_address,
+                enabled: true,
+                max_gas: template.max_gas,
+            };
+
+            // the instance must have the plugin manager as admin like any other plugin
+            validate_plugin(deps.as_ref(), env, &plugin_info)?;
+
+            PLUGINS.save(deps.storage, plugin_info.address.as_str(), &plugin_info)?;
+            Ok(Response::new().add_attributes(vec![
+                ("action", "plugin_instantiated"),
+                ("plugin_address", plugin_info.address.as_str()),
+                ("account", info.sender.as_str()),
+            ]))
+        }
+        ExecuteMsg::PauseAll {} => {
+            assert_owner(deps.storage, &info.sender).map_err(|_| ContractError::Unauthorized {})?;
+
+            PAUSED.save(deps.storage, &true)?;
+            Ok(Response::new().add_attribute("action", "pause_all"))
+        }
+        ExecuteMsg::UnpauseAll {} => {
+            assert_owner(deps.storage, &info.sender).map_err(|_| ContractError::Unauthorized {})?;
+
+            PAUSED.save(deps.storage, &false)?;
+            Ok(Response::new().add_attribute("action", "unpause_all"))
+        }
+        ExecuteMsg::PluginRegistered { plugin_address } => {
+            // only allowed plugins are indexed
+            if !PLUGINS.has(deps.storage, &plugin_address) {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "Plugin not found",
+                )));
+            }
+
+            // the sender is the smart account which registered the plugin,
+            // it must answer that the plugin is registered
+            if !account_has_plugin(deps.as_ref(), &info.sender, &plugin_address)? {
+                return Err(ContractError::PluginNotRegistered {
+                    plugin_address,
+                    account: info.sender.to_string(),
+                });
+            }
+            if !PLUGIN_ACCOUNTS.has(deps.storage, (&plugin_address, &info.sender)) {
+                PLUGIN_ACCOUNTS.save(deps.storage, (&plugin_address, &info.sender), &true)?;
+                PLUGIN_USAGE.update(deps.storage, &plugin_address, |count| -> StdResult<_> {
+                    Ok(count.unwrap_or_default() + 1)
+                })?;
+            }
+
+            Ok(Response::new().add_attributes(vec![
+                ("action", "plugin_registered"),
+                ("plugin_address", plugin_address.as_str()),
+                ("account", info.sender.as_str()),
+            ]))
+        }
+        ExecuteMsg::PluginUnregistered { plugin_address } => {
+            // the smart account must answer that the plugin is not registered anymore,
+            // or that it moved to another plugin manager
+            if account_has_plugin(deps.as_ref(), &info.sender, &plugin_address)? {
+                let account_config: AccountConfigResponse = deps
+                    .querier
+                    .query_wasm_smart(&info.sender, &AccountQueryMsg::Config {})?;
+                if account_config.plugin_manager_addr == env.contract.address {
+                    return Err(ContractError::PluginStillRegistered {
+                        plugin_address,
+                        account: info.sender.to_string(),
+                    });
+                }
+            }
+
+            // the plugin can be disallowed after the smart account registered it
+            if PLUGIN_ACCOUNTS.has(deps.storage, (&plugin_address, &info.sender)) {
+                PLUGIN_ACCOUNTS.remove(deps.storage, (&plugin_address, &info.sender));
+                PLUGIN_USAGE.update(deps.storage, &plugin_address, |count| -> StdResult<_> {
+                    Ok(count.unwrap_or_default().saturating_sub(1))
+                })?;
+            }
+
+            Ok(Response::new().add_attributes(vec![
+                ("action", "plugin_unregistered"),
+                ("plugin_address", plugin_address.as_str()),
+                ("account", info.sender.as_str()),
+            ]))
+        }
     }
 }
 
+// check with the `Plugin` query of a smart account that it has registered a plugin
+fn account_has_plugin(deps: Deps, account: &Addr, plugin_address: &str) -> StdResult<bool> {
+    let response: AccountPluginResponse = deps.querier.query_wasm_smart(
+        account,
+        &AccountQueryMsg::Plugin {
+            address: plugin_address.to_string(),
+        },
+    )?;
+    Ok(response
+        .plugin
+        .map_or(false, |plugin| plugin.contract_address == plugin_address))
+}
+
 // validate plugin info
 // prevent front-run attack
 fn validate_plugin(deps: Deps, env: Env, plugin_info: &Plugin) -> StdResult<()> {
@@ -155,6 +316,11 @@ fn validate_plugin(deps: Deps, env: Env, plugin_info: &Plugin) -> StdResult<()>
         return Err(StdError::generic_err("Invalid plugin code_id"));
     }
 
+    // smart accounts need a gas limit to call the plugin
+    if plugin_info.max_gas == 0 {
+        return Err(StdError::generic_err("Invalid plugin max_gas"));
+    }
+
     // require plugin-manager as plugin admin
     if contract_info.admin.unwrap_or(String::default()) != env.contract.address {
         return Err(StdError::generic_err("Invalid plugin admin"));
@@ -169,8 +335,9 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::PluginInfo { address } => {
             println!("address: {}", address);
+            // the pause of all plugins is reported by `DisabledPlugins`
             let plugin = PLUGINS.load(deps.storage, &address)?;
-            to_json_binary(&PluginResponse::from(plugin.into()))
+            to_json_binary(&PluginResponse::from(plugin))
         }
         QueryMsg::AllPlugins { start_after, limit } => {
             let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
@@ -184,9 +351,53 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 
             to_json_binary(&AllPluginsResponse { plugins })
         }
+        QueryMsg::PluginTemplate { code_id } => {
+            let template = PLUGIN_TEMPLATES.load(deps.storage, code_id)?;
+            to_json_binary(&PluginTemplateResponse::from(template))
+        }
+        QueryMsg::DisabledPlugins { addresses } => {
+            // plugins unknown to this plugin manager are disabled as well
+            let disabled = addresses
+                .into_iter()
+                .filter_map(|address| match PLUGINS.may_load(deps.storage, &address) {
+                    Ok(Some(plugin)) if plugin.enabled => None,
+                    Ok(_) => Some(Ok(address)),
+                    Err(err) => Some(Err(err)),
+                })
+                .collect::<StdResult<_>>()?;
+
+            to_json_binary(&DisabledPluginsResponse {
+                disabled,
+                paused: is_paused(deps)?,
+            })
+        }
+        QueryMsg::PluginAccounts {
+            plugin_address,
+            start_after,
+            limit,
+        } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+
+            let accounts = PLUGIN_ACCOUNTS
+                .prefix(&plugin_address)
+                .keys(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|account| account.to_string()))
+                .collect::<StdResult<_>>()?;
+            let count = PLUGIN_USAGE
+                .may_load(deps.storage, &plugin_address)?
+                .unwrap_or_default();
+
+            to_json_binary(&PluginAccountsResponse { accounts, count })
+        }
     }
 }
 
+fn is_paused(deps: Deps) -> StdResult<bool> {
+    Ok(PAUSED.may_load(deps.storage)?.unwrap_or_default())
+}
+
 /// Handling submessage reply.
 /// For more info on submessage and reply, see https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#submessages
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -9,6 +9,18 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Plugin {plugin_address} is not registered by smart account {account}")]
+    PluginNotRegistered {
+        plugin_address: String,
+        account: String,
+    },
+
+    #[error("Plugin {plugin_address} is still registered by smart account {account}")]
+    PluginStillRegistered {
+        plugin_address: String,
+        account: String,
+    },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
     // Add any other custom errors you like here.
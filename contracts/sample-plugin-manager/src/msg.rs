@@ -1,8 +1,9 @@
-use crate::state::Plugin;
+use crate::state::{Plugin, PluginTemplate};
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Addr;
 use cw_ownable::cw_ownable_execute;
 pub use pyxis_sm::plugin_manager_msg::QueryMsg;
+use serde::Deserialize;
 
 /// Message type for `instantiate` entry_point
 #[cw_serde]
@@ -28,8 +29,54 @@ pub enum ExecuteMsg {
         new_code_id: u64,
         msg: String,
     },
+    AllowPluginTemplate {
+        template: PluginTemplate,
+    },
+    DisallowPluginTemplate {
+        code_id: u64,
+    },
+    // emergency stop of all plugins, smart accounts stop calling them
+    PauseAll {},
+    UnpauseAll {},
+    // sent by a smart account after it registered a plugin
+    PluginRegistered {
+        plugin_address: String,
+    },
+    // sent by a smart account after it unregistered a plugin
+    PluginUnregistered {
+        plugin_address: String,
+    },
+    // sent by a smart account after it instantiated a plugin from a template
+    PluginInstantiated {
+        plugin_address: String,
+    },
 }
 
 /// Message type for `migrate` entry_point
 #[cw_serde]
 pub enum MigrateMsg {}
+
+/// Queries of the smart accounts sending `PluginRegistered` and `PluginUnregistered`
+#[cw_serde]
+pub enum AccountQueryMsg {
+    Config {},
+    Plugin { address: String },
+}
+
+// the fields of the smart account responses read by this plugin manager,
+// the other fields are ignored
+#[derive(Deserialize)]
+pub struct AccountConfigResponse {
+    pub plugin_manager_addr: Addr,
+}
+
+#[derive(Deserialize)]
+pub struct AccountPluginResponse {
+    // None if the plugin is not registered
+    pub plugin: Option<AccountPlugin>,
+}
+
+#[derive(Deserialize)]
+pub struct AccountPlugin {
+    pub contract_address: Addr,
+}
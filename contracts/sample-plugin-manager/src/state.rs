@@ -3,8 +3,10 @@
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Addr;
-use cw_storage_plus::Map;
-use pyxis_sm::plugin_manager_msg::{PluginResponse, PluginType};
+use cw_storage_plus::{Item, Map};
+use pyxis_sm::plugin_manager_msg::{
+    legacy_plugin_max_gas, PluginResponse, PluginTemplateResponse, PluginType,
+};
 
 #[cw_serde]
 pub struct Plugin {
@@ -14,19 +16,56 @@ pub struct Plugin {
     pub version: String,
     pub address: Addr,
     pub enabled: bool,
+    // plugins allowed before the max gas was stored keep the legacy gas limit
+    #[serde(default = "legacy_plugin_max_gas")]
+    pub max_gas: u64,
 }
 
-impl Into<PluginResponse> for Plugin {
-    fn into(self) -> PluginResponse {
+impl From<Plugin> for PluginResponse {
+    fn from(plugin: Plugin) -> Self {
         PluginResponse {
-            name: self.name,
-            plugin_type: self.plugin_type,
-            version: self.version,
-            address: self.address.to_string(),
-            code_id: self.code_id,
-            enabled: self.enabled,
+            name: plugin.name,
+            plugin_type: plugin.plugin_type,
+            version: plugin.version,
+            address: plugin.address.to_string(),
+            code_id: plugin.code_id,
+            enabled: plugin.enabled,
+            max_gas: plugin.max_gas,
+        }
+    }
+}
+
+/// PluginTemplate is a plugin code smart accounts can instantiate their own instances of
+/// the instances are allowed with the information of their template
+#[cw_serde]
+pub struct PluginTemplate {
+    pub name: String,
+    pub plugin_type: PluginType,
+    pub code_id: u64,
+    pub version: String,
+    pub enabled: bool,
+    pub max_gas: u64,
+}
+
+impl From<PluginTemplate> for PluginTemplateResponse {
+    fn from(template: PluginTemplate) -> Self {
+        PluginTemplateResponse {
+            name: template.name,
+            plugin_type: template.plugin_type,
+            version: template.version,
+            code_id: template.code_id,
+            enabled: template.enabled,
+            max_gas: template.max_gas,
         }
     }
 }
 
 pub const PLUGINS: Map<&str, Plugin> = Map::new("plugins");
+pub const PLUGIN_TEMPLATES: Map<u64, PluginTemplate> = Map::new("plugin_templates");
+
+// smart accounts using each plugin, reported by the accounts when they register or unregister it
+pub const PLUGIN_ACCOUNTS: Map<(&str, &Addr), bool> = Map::new("plugin_accounts");
+pub const PLUGIN_USAGE: Map<&str, u64> = Map::new("plugin_usage");
+
+// all plugins are paused, smart accounts stop calling them in their hooks
+pub const PAUSED: Item<bool> = Item::new("paused");
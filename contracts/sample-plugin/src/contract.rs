@@ -1,7 +1,7 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+    to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
 };
 use cw2::set_contract_version;
 
@@ -9,12 +9,18 @@ use crate::error::ContractError;
 use crate::msg::{InstantiateMsg, MigrateMsg, QueryMsg};
 use crate::state::{UserConfig, USER_CONFIGS};
 
-use pyxis_sm::msg::{CallInfo, PyxisPluginExecuteMsg, SdkMsg};
+use pyxis_sm::msg::{
+    BalanceChange, CallInfo, InterfaceInfoResponse, PluginHook, PyxisPluginExecuteMsg, SdkMsg,
+    TrackedDenomsResponse, ValidateExecuteResponse, PLUGIN_INTERFACE_VERSION,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:sample-plugin";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// the sample plugin reports the balance changes of the native denom
+const TRACKED_DENOM: &str = "uaura";
+
 /// Handling contract instantiation
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -58,12 +64,21 @@ pub fn execute(
     match msg {
         PyxisPluginExecuteMsg::Register { config } => handle_register(deps, env, info, config),
         PyxisPluginExecuteMsg::Unregister {} => handle_unregister(deps, env, info),
+        PyxisPluginExecuteMsg::UpdateConfig { config } => {
+            handle_update_config(deps, env, info, config)
+        }
         PyxisPluginExecuteMsg::PreExecute {
             msgs,
             call_info,
             is_authz,
+            ..
         } => handle_pre_execute(deps, env, info, msgs, call_info, is_authz),
-        PyxisPluginExecuteMsg::AfterExecute { .. } => Ok(Response::new()),
+        PyxisPluginExecuteMsg::AfterExecute {
+            msgs,
+            call_info,
+            balance_changes,
+            ..
+        } => handle_after_execute(deps, env, info, msgs, call_info, balance_changes),
     }
 }
 
@@ -110,6 +125,24 @@ pub fn handle_unregister(
     Ok(Response::default())
 }
 
+// TODO: Implement your custom logic here
+/// Handling update config message
+/// This is just a sample implementation.
+pub fn handle_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    config: String,
+) -> Result<Response, ContractError> {
+    // only a registered address can update its config
+    let mut user_config = USER_CONFIGS.load(deps.storage, &info.sender)?;
+
+    user_config.config = config;
+    USER_CONFIGS.save(deps.storage, &info.sender, &user_config)?;
+
+    Ok(Response::default())
+}
+
 pub fn handle_pre_execute(
     deps: DepsMut,
     _env: Env,
@@ -119,17 +152,10 @@ pub fn handle_pre_execute(
     _is_authz: bool,
 ) -> Result<Response, ContractError> {
     // load config of sender
-    let user_config = USER_CONFIGS.load(deps.storage, &info.sender).unwrap();
+    let user_config = USER_CONFIGS.load(deps.storage, &info.sender)?;
 
-    match user_config.config.as_str() {
-        "approve" => Ok(Response::new()),
-        "reject" => Err(ContractError::Rejected {
-            reason: "reject".to_string(),
-        }),
-        _ => Err(ContractError::Rejected {
-            reason: "config error".to_string(),
-        }),
-    }
+    check_config(&user_config)?;
+    Ok(Response::new())
 }
 
 pub fn handle_after_execute(
@@ -138,12 +164,26 @@ pub fn handle_after_execute(
     info: MessageInfo,
     _msgs: Vec<SdkMsg>,
     _call_info: CallInfo,
+    balance_changes: Vec<BalanceChange>,
 ) -> Result<Response, ContractError> {
     // load config of sender
-    let user_config = USER_CONFIGS.load(deps.storage, &info.sender).unwrap();
+    let user_config = USER_CONFIGS.load(deps.storage, &info.sender)?;
+
+    check_config(&user_config)?;
+    Ok(
+        Response::new().add_attributes(balance_changes.iter().map(|change| {
+            (
+                "balance_change",
+                format!("{}:{}:{}", change.denom, change.before, change.after),
+            )
+        })),
+    )
+}
 
+/// the sample rule: approve or reject every transaction depending on the config
+fn check_config(user_config: &UserConfig) -> Result<(), ContractError> {
     match user_config.config.as_str() {
-        "approve" => Ok(Response::new()),
+        "approve" => Ok(()),
         "reject" => Err(ContractError::Rejected {
             reason: "reject".to_string(),
         }),
@@ -155,12 +195,37 @@ pub fn handle_after_execute(
 
 /// Handling contract query
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(_deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config { address: _ } => to_json_binary(&String::default()),
+        QueryMsg::ValidateExecute { address, .. } => {
+            to_json_binary(&query_validate_execute(deps, address)?)
+        }
+        QueryMsg::InterfaceInfo {} => to_json_binary(&InterfaceInfoResponse {
+            version: PLUGIN_INTERFACE_VERSION,
+            hooks: vec![PluginHook::PreExecute, PluginHook::AfterExecute],
+        }),
+        QueryMsg::TrackedDenoms { address: _ } => to_json_binary(&TrackedDenomsResponse {
+            denoms: vec![TRACKED_DENOM.to_string()],
+        }),
     }
 }
 
+fn query_validate_execute(deps: Deps, address: String) -> StdResult<ValidateExecuteResponse> {
+    let user_config = USER_CONFIGS.load(deps.storage, &Addr::unchecked(address))?;
+
+    Ok(match check_config(&user_config) {
+        Ok(()) => ValidateExecuteResponse {
+            valid: true,
+            reason: None,
+        },
+        Err(err) => ValidateExecuteResponse {
+            valid: false,
+            reason: Some(err.to_string()),
+        },
+    })
+}
+
 /// Handling submessage reply.
 /// For more info on submessage and reply, see https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#submessages
 #[cfg_attr(not(feature = "library"), entry_point)]
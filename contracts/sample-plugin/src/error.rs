@@ -9,7 +9,7 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
-    #[error("Rejected")]
+    #[error("Rejected: {reason}")]
     Rejected { reason: String },
 
     #[error("Custom Error val: {val:?}")]
@@ -7,7 +7,11 @@ use cw2::set_contract_version;
 
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{RecoveryConfig, CONFIG_MAP};
+use crate::state::{RecoveryConfig, CONFIG_MAP, NEXT_NONCES};
+use pyxis_sm::msg::{
+    InterfaceInfoResponse, PluginHook, TrackedDenomsResponse, ValidateExecuteResponse,
+    PLUGIN_INTERFACE_VERSION,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:simple-recovery-plugin";
@@ -56,11 +60,13 @@ pub fn execute(
     match msg {
         ExecuteMsg::Register { config } => handle_register(deps, env, info, config),
         ExecuteMsg::Unregister {} => handle_unregister(deps, env, info),
+        ExecuteMsg::UpdateConfig { config } => handle_update_config(deps, env, info, config),
         ExecuteMsg::Recover {
             caller,
             pub_key,
             credentials,
-        } => handle_recover(deps, env, info, caller, pub_key, credentials),
+            nonce,
+        } => handle_recover(deps, env, info, caller, pub_key, credentials, nonce),
     }
 }
 
@@ -73,7 +79,7 @@ fn handle_register(
     info: MessageInfo,
     config: String,
 ) -> Result<Response, ContractError> {
-    let parsed_config = RecoveryConfig::from(config);
+    let parsed_config = RecoveryConfig::try_from(config)?;
 
     // sender should be the smart account address
     if info.sender != parsed_config.smart_account_address {
@@ -102,6 +108,33 @@ fn handle_unregister(
     Ok(Response::new())
 }
 
+/// When updating the config of a registered address
+/// - make sure the address is registered
+/// - parse and store the new config
+fn handle_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    config: String,
+) -> Result<Response, ContractError> {
+    // load config of sender to make sure it is registered
+    let _config = CONFIG_MAP.load(deps.storage, &info.sender)?;
+
+    let parsed_config = RecoveryConfig::try_from(config)?;
+
+    // sender should be the smart account address
+    if info.sender != parsed_config.smart_account_address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    CONFIG_MAP.save(deps.storage, &info.sender, &parsed_config)?;
+
+    Ok(Response::new())
+}
+
+/// When a registered address is recovered
+/// - make sure the caller is the recover address
+/// - make sure the nonce has not been used, an approval can not be replayed
 fn handle_recover(
     deps: DepsMut,
     _env: Env,
@@ -109,6 +142,7 @@ fn handle_recover(
     caller: String,
     _pub_key: Binary,
     _credentials: Binary,
+    nonce: u64,
 ) -> Result<Response, ContractError> {
     // load config of sender
     let config = CONFIG_MAP.load(deps.storage, &info.sender)?;
@@ -118,8 +152,18 @@ fn handle_recover(
         return Err(ContractError::Unauthorized {});
     }
 
+    // the smart account increases its nonce after every successful recovery
+    let next_nonce = NEXT_NONCES
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if nonce < next_nonce {
+        return Err(ContractError::NonceAlreadyUsed { nonce, next_nonce });
+    }
+    NEXT_NONCES.save(deps.storage, &info.sender, &(nonce + 1))?;
+
     // we will ignore pubkey and credentials as we don't need them for this example
-    Ok(Response::new())
+    // a plugin verifying signed credentials would check that they commit to the nonce
+    Ok(Response::new().add_attribute("nonce", nonce.to_string()))
 }
 
 /// Handling contract query
@@ -127,6 +171,16 @@ fn handle_recover(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config { address } => to_json_binary(&query_config(deps, address)),
+        // recovery plugins are not called in `AfterExecute`, they never reject a transaction
+        QueryMsg::ValidateExecute { .. } => to_json_binary(&ValidateExecuteResponse {
+            valid: true,
+            reason: None,
+        }),
+        QueryMsg::InterfaceInfo {} => to_json_binary(&InterfaceInfoResponse {
+            version: PLUGIN_INTERFACE_VERSION,
+            hooks: vec![PluginHook::Recover],
+        }),
+        QueryMsg::TrackedDenoms { .. } => to_json_binary(&TrackedDenomsResponse { denoms: vec![] }),
     }
 }
 
@@ -9,6 +9,12 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Recovery nonce {nonce} has already been used, next nonce is {next_nonce}")]
+    NonceAlreadyUsed { nonce: u64, next_nonce: u64 },
+
+    #[error("Invalid recovery config: {config}")]
+    InvalidConfig { config: String },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
     // Add any other custom errors you like here.
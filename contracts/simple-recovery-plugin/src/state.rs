@@ -5,16 +5,25 @@ use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Addr;
 use cw_storage_plus::Map;
 
+use crate::error::ContractError;
+
 #[cw_serde]
 pub struct RecoveryConfig {
     pub smart_account_address: Addr,
     pub recover_address: Addr,
 }
 
-impl From<String> for RecoveryConfig {
-    fn from(config: String) -> Self {
-        serde_json_wasm::from_str::<RecoveryConfig>(&config).unwrap()
+impl TryFrom<String> for RecoveryConfig {
+    type Error = ContractError;
+
+    fn try_from(config: String) -> Result<Self, Self::Error> {
+        serde_json_wasm::from_str::<RecoveryConfig>(&config)
+            .map_err(|_| ContractError::InvalidConfig { config })
     }
 }
 
 pub const CONFIG_MAP: Map<&Addr, RecoveryConfig> = Map::new("config");
+
+// lowest recovery nonce of each smart account which has not been used yet
+// it is kept when the plugin is unregistered so that used nonces stay invalid
+pub const NEXT_NONCES: Map<&Addr, u64> = Map::new("next_nonces");
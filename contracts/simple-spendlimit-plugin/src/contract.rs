@@ -6,11 +6,14 @@ use crate::tracked_msgs::get_transfer_balances;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
-    Storage, Uint128, Uint64,
+    to_json_binary, Addr, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Reply, Response,
+    StdResult, Storage, Uint128, Uint64,
 };
 use cw2::set_contract_version;
-use pyxis_sm::msg::{CallInfo, SdkMsg};
+use pyxis_sm::msg::{
+    BalanceChange, CallInfo, InterfaceInfoResponse, PluginHook, SdkMsg, TrackedDenomsResponse,
+    ValidateExecuteResponse, PLUGIN_INTERFACE_VERSION,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:simple-spendlimit-plugin";
@@ -62,14 +65,18 @@ pub fn execute(
             msgs,
             call_info,
             is_authz,
+            ..
         } => handle_pre_execute(deps, env, info, msgs, call_info, is_authz),
         ExecuteMsg::AfterExecute {
             msgs,
             call_info,
             is_authz,
-        } => handle_after_execute(deps, env, info, msgs, call_info, is_authz),
+            balance_changes,
+            ..
+        } => handle_after_execute(deps, env, info, msgs, call_info, is_authz, balance_changes),
         ExecuteMsg::Register { config } => handle_register(deps, env, info, config),
         ExecuteMsg::Unregister {} => handle_unregister(deps, env, info),
+        ExecuteMsg::UpdateConfig { config } => handle_update_config(deps, env, info, config),
 
         ExecuteMsg::AddLimit { limit } => handle_add_limit(deps, env, info, limit),
         ExecuteMsg::UpdateLimit { index, limit } => {
@@ -97,17 +104,59 @@ fn handle_after_execute(
     msgs: Vec<SdkMsg>,
     _call_info: CallInfo,
     _is_authz: bool,
+    balance_changes: Vec<BalanceChange>,
 ) -> Result<Response, ContractError> {
     // load sender's limits
     let mut limits = load_limits(deps.storage, &info.sender)?;
 
     // get transfer balances of transaction
-    let transfer_balances = get_transfer_balances(msgs)?;
+    let transfer_balances = spent_balances(msgs, &balance_changes)?;
 
     if transfer_balances.len() == 0 {
         return Ok(Response::new());
     }
 
+    apply_limits(&mut limits, &env, &transfer_balances)?;
+
+    // update limits
+    LIMITS.save(deps.storage, &info.sender, &limits)?;
+
+    Ok(Response::new().add_attribute("action", "after_execute"))
+}
+
+/// the funds spent by a transaction in each denom, the larger of the amount sent by the messages
+/// and the decrease of the balance reported by the smart account:
+/// the balance misses funds received back in the same transaction,
+/// the messages miss funds moved by messages which are not tracked
+fn spent_balances(
+    msgs: Vec<SdkMsg>,
+    balance_changes: &[BalanceChange],
+) -> Result<Vec<Coin>, ContractError> {
+    let mut spent = get_transfer_balances(msgs)?;
+    for change in balance_changes
+        .iter()
+        .filter(|change| change.after < change.before)
+    {
+        let decrease = change.before - change.after;
+        match spent.iter_mut().find(|coin| coin.denom == change.denom) {
+            Some(coin) => coin.amount = coin.amount.max(decrease),
+            None => spent.push(Coin {
+                denom: change.denom.clone(),
+                amount: decrease,
+            }),
+        }
+    }
+
+    Ok(spent)
+}
+
+/// check the transfer balances of a transaction against the limits
+/// and add them to the usage of the periodic limits
+fn apply_limits(
+    limits: &mut [Limit],
+    env: &Env,
+    transfer_balances: &[Coin],
+) -> Result<(), ContractError> {
     for limit in limits.iter_mut() {
         match limit {
             Limit::PerTransaction(l) => {
@@ -162,10 +211,7 @@ fn handle_after_execute(
         }
     }
 
-    // update limits
-    LIMITS.save(deps.storage, &info.sender, &limits)?;
-
-    Ok(Response::new().add_attribute("action", "after_execute"))
+    Ok(())
 }
 
 fn handle_register(
@@ -197,6 +243,21 @@ fn handle_unregister(
         .add_attribute("account", info.sender))
 }
 
+fn handle_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    _config: String,
+) -> Result<Response, ContractError> {
+    // limits are managed with `AddLimit`, `UpdateLimit` and `DeleteLimit`
+    // the config is not used, only make sure the account is registered
+    load_limits(deps.storage, &info.sender)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_config")
+        .add_attribute("account", info.sender))
+}
+
 fn handle_add_limit(
     deps: DepsMut,
     env: Env,
@@ -294,10 +355,62 @@ fn validate_limit(limit: &Limit, env: Env) -> Result<(), ContractError> {
 
 /// Handling contract query
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetLimits { address } => to_json_binary(&get_limits(deps, address)?),
         QueryMsg::Config { address: _ } => to_json_binary(&String::default()),
+        QueryMsg::ValidateExecute { address, msgs, .. } => {
+            to_json_binary(&validate_execute(deps, env, address, msgs))
+        }
+        QueryMsg::InterfaceInfo {} => to_json_binary(&InterfaceInfoResponse {
+            version: PLUGIN_INTERFACE_VERSION,
+            hooks: vec![PluginHook::PreExecute, PluginHook::AfterExecute],
+        }),
+        QueryMsg::TrackedDenoms { address } => {
+            to_json_binary(&tracked_denoms(deps, Addr::unchecked(address))?)
+        }
+    }
+}
+
+/// the smart account reports the balance changes of the denoms of its limits
+fn tracked_denoms(deps: Deps, address: Addr) -> StdResult<TrackedDenomsResponse> {
+    let limits = LIMITS.may_load(deps.storage, &address)?.unwrap_or_default();
+
+    let mut denoms: Vec<String> = Vec::new();
+    for limit in limits {
+        let denom = match limit {
+            Limit::PerTransaction(l) => l.limit.denom,
+            Limit::Periodic(l) => l.limit.denom,
+        };
+        if !denoms.contains(&denom) {
+            denoms.push(denom);
+        }
+    }
+
+    Ok(TrackedDenomsResponse { denoms })
+}
+
+/// same checks as `handle_after_execute`, the usage of the limits is not saved
+fn validate_execute(
+    deps: Deps,
+    env: Env,
+    address: String,
+    msgs: Vec<SdkMsg>,
+) -> ValidateExecuteResponse {
+    let result = load_limits(deps.storage, &Addr::unchecked(address)).and_then(|mut limits| {
+        let transfer_balances = get_transfer_balances(msgs)?;
+        apply_limits(&mut limits, &env, &transfer_balances)
+    });
+
+    match result {
+        Ok(()) => ValidateExecuteResponse {
+            valid: true,
+            reason: None,
+        },
+        Err(err) => ValidateExecuteResponse {
+            valid: false,
+            reason: Some(err.to_string()),
+        },
     }
 }
 
@@ -1,2 +1,87 @@
 #[cfg(test)]
-mod unit_tests {}
+mod unit_tests {
+    use cosmos_sdk_proto::cosmos::bank::v1beta1::MsgSend;
+    use cosmos_sdk_proto::cosmos::base::v1beta1::Coin as ProtoCoin;
+    use cosmos_sdk_proto::traits::Message;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coin, Binary, Uint128};
+    use pyxis_sm::msg::{BalanceChange, CallInfo, SdkMsg};
+
+    use crate::contract::execute;
+    use crate::msg::ExecuteMsg;
+    use crate::state::{Limit, LimitPerTransaction};
+    use crate::ContractError;
+
+    const SMART_ACCOUNT: &str = "smart_account";
+
+    fn bank_send(amount: u128) -> SdkMsg {
+        SdkMsg {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+            value: Binary::from(
+                MsgSend {
+                    from_address: SMART_ACCOUNT.to_string(),
+                    to_address: "receiver".to_string(),
+                    amount: vec![ProtoCoin {
+                        denom: "uaura".to_string(),
+                        amount: amount.to_string(),
+                    }],
+                }
+                .encode_to_vec(),
+            ),
+        }
+    }
+
+    fn after_execute(msgs: Vec<SdkMsg>, before: u128, after: u128) -> ExecuteMsg {
+        ExecuteMsg::AfterExecute {
+            msgs,
+            call_info: CallInfo::default(),
+            is_authz: false,
+            tx_id: 1,
+            balance_changes: vec![BalanceChange {
+                denom: "uaura".to_string(),
+                before: Uint128::new(before),
+                after: Uint128::new(after),
+            }],
+        }
+    }
+
+    #[test]
+    fn after_execute_enforces_the_larger_spending() {
+        let mut deps = mock_dependencies();
+        let info = mock_info(SMART_ACCOUNT, &[]);
+
+        for msg in [
+            ExecuteMsg::Register {
+                config: "".to_string(),
+            },
+            ExecuteMsg::AddLimit {
+                limit: Limit::PerTransaction(LimitPerTransaction {
+                    limit: coin(30, "uaura"),
+                }),
+            },
+        ] {
+            execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        }
+
+        // funds moved by messages which are not tracked still decrease the balance,
+        // funds received back in the transaction hide the messages from the balance
+        for msg in [
+            after_execute(vec![bank_send(10)], 100, 50),
+            after_execute(vec![bank_send(50)], 100, 90),
+        ] {
+            let response = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+            assert!(matches!(
+                response,
+                Err(ContractError::ReachTransactionSpendLimit { .. })
+            ));
+        }
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            after_execute(vec![bank_send(20)], 100, 80),
+        );
+        assert!(response.is_ok());
+    }
+}
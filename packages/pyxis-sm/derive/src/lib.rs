@@ -71,6 +71,8 @@ fn merge_variants(metadata: TokenStream, left: TokenStream, right: TokenStream)
 ///         msgs: Vec<::pyxis_sm::msg::SdkMsg>,
 ///         call_info: ::pyxis_sm::msg::CallInfo,
 ///         is_authz: bool,
+///         tx_id: u64,
+///         balance_changes: Vec<::pyxis_sm::msg::BalanceChange>,
 ///         },
 ///     Foo {},
 ///     Bar {},
@@ -89,17 +91,24 @@ pub fn base_plugin_execute(metadata: TokenStream, input: TokenStream) -> TokenSt
                 Register { config: String },
                 /// Unregister a plugin from this smart account, the caller must be the smart account itself
                 Unregister {},
+                /// Update the config of a registered plugin, the caller must be the smart account itself
+                UpdateConfig { config: String },
                 /// PreExecute is called before a transaction is executed
+                /// `tx_id` is the same in the `AfterExecute` of the transaction
                 PreExecute {
                     msgs: Vec<::pyxis_sm::msg::SdkMsg>,
                     call_info: ::pyxis_sm::msg::CallInfo,
                     is_authz: bool,
+                    tx_id: u64,
                 },
                 /// AfterExecute is called at the end of a transaction
+                /// `balance_changes` are reported for the denoms returned by `TrackedDenoms`
                 AfterExecute {
                     msgs: Vec<::pyxis_sm::msg::SdkMsg>,
                     call_info: ::pyxis_sm::msg::CallInfo,
                     is_authz: bool,
+                    tx_id: u64,
+                    balance_changes: Vec<::pyxis_sm::msg::BalanceChange>,
                 },
             }
         }
@@ -121,11 +130,17 @@ pub fn recovery_plugin_execute(metadata: TokenStream, input: TokenStream) -> Tok
                 },
                 /// Unregister a plugin from this smart account, the caller must be the smart account itself
                 Unregister {},
+                /// Update the config of a registered plugin, the caller must be the smart account itself
+                UpdateConfig {
+                    config: String,
+                },
                 /// Validate smart account recovery action
+                /// `nonce` is increased by the smart account after every successful recovery
                 Recover {
                     caller: String,
                     pub_key: cosmwasm_std::Binary,
                     credentials: cosmwasm_std::Binary,
+                    nonce: u64,
                 },
             }
         }
@@ -145,6 +160,23 @@ pub fn base_plugin_query(metadata: TokenStream, input: TokenStream) -> TokenStre
                 #[returns(String)]
                 Config {
                     address: String,
+                },
+                /// Check the messages of a transaction of a smart account with the rules of `AfterExecute`
+                /// without changing the state of the plugin
+                #[returns(::pyxis_sm::msg::ValidateExecuteResponse)]
+                ValidateExecute {
+                    address: String,
+                    msgs: Vec<::pyxis_sm::msg::SdkMsg>,
+                    call_info: ::pyxis_sm::msg::CallInfo,
+                    is_authz: bool,
+                },
+                /// Query for the version of the plugin interface and the hooks implemented by the plugin
+                #[returns(::pyxis_sm::msg::InterfaceInfoResponse)]
+                InterfaceInfo {},
+                /// Query for the denoms whose balance changes a smart account should report in `AfterExecute`
+                #[returns(::pyxis_sm::msg::TrackedDenomsResponse)]
+                TrackedDenoms {
+                    address: String,
                 }
             }
         }
@@ -1,5 +1,21 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Binary, Coin};
+use cosmwasm_std::{to_json_binary, Binary, Coin, StdResult, Uint128};
+
+/// version of the hook messages sent by smart accounts to their plugins,
+/// increased every time a hook message changes
+pub const PLUGIN_INTERFACE_VERSION: u32 = 4;
+
+/// version of the plugins which do not answer the `InterfaceInfo` query
+pub const LEGACY_PLUGIN_INTERFACE_VERSION: u32 = 1;
+
+/// first version of the plugin interface passing the recovery nonce to recovery plugins
+pub const RECOVERY_NONCE_INTERFACE_VERSION: u32 = 2;
+
+/// first version of the plugin interface passing the transaction id to the hooks
+pub const TX_ID_INTERFACE_VERSION: u32 = 3;
+
+/// first version of the plugin interface reporting balance changes in `AfterExecute`
+pub const BALANCE_CHANGES_INTERFACE_VERSION: u32 = 4;
 
 #[cw_serde]
 pub struct CallInfo {
@@ -26,6 +42,16 @@ pub struct SdkMsg {
     pub value: Binary,
 }
 
+/// BalanceChange is the change of the balance of a smart account in a denom during a transaction
+#[cw_serde]
+pub struct BalanceChange {
+    pub denom: String,
+    // balance of the smart account before the transaction
+    pub before: Uint128,
+    // balance of the smart account after the transaction
+    pub after: Uint128,
+}
+
 #[cw_serde]
 pub enum PyxisSudoMsg {
     // pre_execute is a base message which is called before any other message
@@ -54,29 +80,208 @@ pub enum PyxisPluginExecuteMsg {
     Register { config: String },
     /// Unregister a plugin from this smart account, the caller must be the smart account itself
     Unregister {},
+    /// Update the config of a registered plugin, the caller must be the smart account itself
+    UpdateConfig { config: String },
     /// PreExecute is called before a transaction is executed
     PreExecute {
         msgs: Vec<SdkMsg>,
         call_info: CallInfo,
         is_authz: bool,
+        // id of the transaction in the smart account, the same in `PreExecute` and `AfterExecute`
+        tx_id: u64,
     },
     /// AfterExecute is called at the end of a transaction
     AfterExecute {
         msgs: Vec<SdkMsg>,
         call_info: CallInfo,
         is_authz: bool,
+        // id of the transaction in the smart account, the same in `PreExecute` and `AfterExecute`
+        tx_id: u64,
+        // balance changes of the smart account in the denoms tracked by its plugins
+        balance_changes: Vec<BalanceChange>,
     },
 }
 
+impl PyxisPluginExecuteMsg {
+    /// encode the message in the given version of the plugin interface
+    pub fn to_versioned_binary(&self, interface_version: u32) -> StdResult<Binary> {
+        match self {
+            PyxisPluginExecuteMsg::PreExecute {
+                msgs,
+                call_info,
+                is_authz,
+                ..
+            } if interface_version < TX_ID_INTERFACE_VERSION => {
+                to_json_binary(&v1::PyxisPluginExecuteMsg::PreExecute {
+                    msgs: msgs.clone(),
+                    call_info: call_info.clone(),
+                    is_authz: *is_authz,
+                })
+            }
+            PyxisPluginExecuteMsg::AfterExecute {
+                msgs,
+                call_info,
+                is_authz,
+                ..
+            } if interface_version < TX_ID_INTERFACE_VERSION => {
+                to_json_binary(&v1::PyxisPluginExecuteMsg::AfterExecute {
+                    msgs: msgs.clone(),
+                    call_info: call_info.clone(),
+                    is_authz: *is_authz,
+                })
+            }
+            PyxisPluginExecuteMsg::AfterExecute {
+                msgs,
+                call_info,
+                is_authz,
+                tx_id,
+                ..
+            } if interface_version < BALANCE_CHANGES_INTERFACE_VERSION => {
+                to_json_binary(&v3::PyxisPluginExecuteMsg::AfterExecute {
+                    msgs: msgs.clone(),
+                    call_info: call_info.clone(),
+                    is_authz: *is_authz,
+                    tx_id: *tx_id,
+                })
+            }
+            _ => to_json_binary(self),
+        }
+    }
+}
+
+/// Base queries of a plugin, appended to the plugin query message by `base_plugin_query`
+#[cw_serde]
+pub enum PyxisPluginQueryMsg {
+    /// Query for current configuration given a smart account address
+    Config { address: String },
+    /// Check the messages of a transaction of a smart account with the rules of `AfterExecute`
+    /// without changing the state of the plugin
+    ValidateExecute {
+        address: String,
+        msgs: Vec<SdkMsg>,
+        call_info: CallInfo,
+        is_authz: bool,
+    },
+    /// Query for the version of the plugin interface and the hooks implemented by the plugin
+    InterfaceInfo {},
+    /// Query for the denoms whose balance changes a smart account should report in `AfterExecute`
+    TrackedDenoms { address: String },
+}
+
+/// Hooks a plugin can implement
+#[cw_serde]
+pub enum PluginHook {
+    PreExecute,
+    AfterExecute,
+    Recover,
+}
+
+#[cw_serde]
+pub struct TrackedDenomsResponse {
+    pub denoms: Vec<String>,
+}
+
+#[cw_serde]
+pub struct InterfaceInfoResponse {
+    // version of the hook messages understood by the plugin
+    pub version: u32,
+    // hooks implemented by the plugin
+    pub hooks: Vec<PluginHook>,
+}
+
+#[cw_serde]
+pub struct ValidateExecuteResponse {
+    // whether `AfterExecute` would accept the transaction
+    pub valid: bool,
+    // reason of the rejection
+    pub reason: Option<String>,
+}
+
 #[cw_serde]
 pub enum PyxisRecoveryPluginExecuteMsg {
     Register {
         config: String,
     },
     Unregister {},
+    UpdateConfig {
+        config: String,
+    },
     Recover {
         caller: String,
         pub_key: Binary,
         credentials: Binary,
+        // recovery nonce of the smart account, increased after every successful recovery
+        // credentials should commit to it so that they can not be replayed
+        nonce: u64,
     },
 }
+
+impl PyxisRecoveryPluginExecuteMsg {
+    /// encode the message in the given version of the plugin interface
+    pub fn to_versioned_binary(&self, interface_version: u32) -> StdResult<Binary> {
+        match self {
+            PyxisRecoveryPluginExecuteMsg::Recover {
+                caller,
+                pub_key,
+                credentials,
+                ..
+            } if interface_version < RECOVERY_NONCE_INTERFACE_VERSION => {
+                to_json_binary(&v1::PyxisRecoveryPluginExecuteMsg::Recover {
+                    caller: caller.clone(),
+                    pub_key: pub_key.clone(),
+                    credentials: credentials.clone(),
+                })
+            }
+            _ => to_json_binary(self),
+        }
+    }
+}
+
+/// hook messages of the first version of the plugin interface
+/// `PyxisPluginExecuteMsg` did not change in the second version
+pub mod v1 {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::Binary;
+
+    use super::{CallInfo, SdkMsg};
+
+    #[cw_serde]
+    pub enum PyxisPluginExecuteMsg {
+        PreExecute {
+            msgs: Vec<SdkMsg>,
+            call_info: CallInfo,
+            is_authz: bool,
+        },
+        AfterExecute {
+            msgs: Vec<SdkMsg>,
+            call_info: CallInfo,
+            is_authz: bool,
+        },
+    }
+
+    #[cw_serde]
+    pub enum PyxisRecoveryPluginExecuteMsg {
+        Recover {
+            caller: String,
+            pub_key: Binary,
+            credentials: Binary,
+        },
+    }
+}
+
+/// hook messages of the third version of the plugin interface
+pub mod v3 {
+    use cosmwasm_schema::cw_serde;
+
+    use super::{CallInfo, SdkMsg};
+
+    #[cw_serde]
+    pub enum PyxisPluginExecuteMsg {
+        AfterExecute {
+            msgs: Vec<SdkMsg>,
+            call_info: CallInfo,
+            is_authz: bool,
+            tx_id: u64,
+        },
+    }
+}
@@ -1,6 +1,24 @@
 /// Messages and related struct definitions for the plugin manager.
 use cosmwasm_schema::{cw_serde, QueryResponses};
 
+/// gas limit of the plugins allowed before plugin managers declared their max gas
+pub const LEGACY_PLUGIN_MAX_GAS: u64 = 1_000_000;
+
+pub fn legacy_plugin_max_gas() -> u64 {
+    LEGACY_PLUGIN_MAX_GAS
+}
+
+/// Messages sent by smart accounts to notify their plugin manager
+/// the plugin manager keeps the accounts using each plugin from these messages
+#[cw_serde]
+pub enum ExecuteMsg {
+    PluginRegistered { plugin_address: String },
+    PluginUnregistered { plugin_address: String },
+    // sent by a smart account after it instantiated a plugin from a template
+    // the plugin manager allows the instance with the information of the template
+    PluginInstantiated { plugin_address: String },
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -12,6 +30,22 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+
+    // smart accounts which reported they registered a plugin
+    #[returns(PluginAccountsResponse)]
+    PluginAccounts {
+        plugin_address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // plugin code smart accounts can instantiate their own instances of
+    #[returns(PluginTemplateResponse)]
+    PluginTemplate { code_id: u64 },
+
+    // plugins smart accounts must stop calling, among the given plugins
+    #[returns(DisabledPluginsResponse)]
+    DisabledPlugins { addresses: Vec<String> },
 }
 
 #[cw_serde]
@@ -28,9 +62,38 @@ pub struct PluginResponse {
     pub address: String,
     pub code_id: u64,
     pub enabled: bool,
+    // maximum gas a plugin can use each time it is called,
+    // plugin managers which do not report it allow the legacy gas limit
+    #[serde(default = "legacy_plugin_max_gas")]
+    pub max_gas: u64,
+}
+
+#[cw_serde]
+pub struct DisabledPluginsResponse {
+    // given plugins disabled or disallowed by the plugin manager
+    pub disabled: Vec<String>,
+    // all plugins are paused by the plugin manager
+    pub paused: bool,
+}
+
+#[cw_serde]
+pub struct PluginTemplateResponse {
+    pub name: String,
+    pub plugin_type: PluginType,
+    pub version: String,
+    pub code_id: u64,
+    pub enabled: bool,
+    pub max_gas: u64,
 }
 
 #[cw_serde]
 pub struct AllPluginsResponse {
     pub plugins: Vec<PluginResponse>,
 }
+
+#[cw_serde]
+pub struct PluginAccountsResponse {
+    pub accounts: Vec<String>,
+    // number of smart accounts using the plugin
+    pub count: u64,
+}